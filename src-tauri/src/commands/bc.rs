@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use mf_model::{id_generator::IdGenerator, node_pool::NodePool, types::NodeId};
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    commands::{AddRequest, ShareCommand},
+    nodes::{fbfx_csxm::DE_STR, gcxm::BC_LIBRARY_ATTR, rcj::RCJ_STR},
+    utils::{
+        money::{decimal_value, Decimal},
+        price::RcjKind,
+    },
+};
+
+/// 补充定额编码前缀 例如 补子目1
+pub const BC_DE_PREFIX: &str = "补子目";
+
+/// 补充人材机定义
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BcRcj {
+    #[serde(default)]
+    pub material_code: String,
+    pub material_name: String,
+    #[serde(default)]
+    pub specification: String,
+    #[serde(default)]
+    pub unit: String,
+    /// 人工费/材料费/机械费/设备费/主材费
+    #[serde(default)]
+    pub r#type: String,
+    #[serde(default)]
+    pub price_market: Decimal,
+    /// 作为补充定额组成时的消耗量
+    #[serde(default)]
+    pub res_qty: Decimal,
+}
+
+impl BcRcj {
+    pub fn to_attrs(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            (
+                "materialCode".to_string(),
+                self.material_code.clone().into(),
+            ),
+            (
+                "materialName".to_string(),
+                self.material_name.clone().into(),
+            ),
+            (
+                "specification".to_string(),
+                self.specification.clone().into(),
+            ),
+            ("unit".to_string(), self.unit.clone().into()),
+            ("type".to_string(), self.r#type.clone().into()),
+            ("priceMarket".to_string(), decimal_value(self.price_market)),
+            ("resQty".to_string(), decimal_value(self.res_qty)),
+        ])
+    }
+}
+
+/// 补充定额定义
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BcDe {
+    #[serde(default)]
+    pub project_code: String,
+    pub project_name: String,
+    #[serde(default)]
+    pub unit: String,
+    /// 定额下的人材机组成
+    #[serde(default)]
+    pub rcjs: Vec<BcRcj>,
+}
+
+impl BcDe {
+    pub fn to_attrs(&self) -> HashMap<String, Value> {
+        HashMap::from([
+            ("projectCode".to_string(), self.project_code.clone().into()),
+            ("projectName".to_string(), self.project_name.clone().into()),
+            ("unit".to_string(), self.unit.clone().into()),
+            ("typeName".to_string(), "补充定额".into()),
+        ])
+    }
+}
+
+/// 补充库 本地库与工程文件中使用同一结构
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct BcLibrary {
+    #[serde(default)]
+    pub de: Vec<BcDe>,
+    #[serde(default)]
+    pub rcj: Vec<BcRcj>,
+}
+
+impl BcLibrary {
+    /// 本地库文件名
+    pub const LOCAL_NAME: &'static str = "bc";
+
+    /// 读取工程项目中保存的补充库
+    pub fn from_doc(doc: &NodePool, root_id: &NodeId) -> Self {
+        doc.get_node(root_id)
+            .and_then(|root| root.attrs.get_value::<BcLibrary>(BC_LIBRARY_ATTR))
+            .unwrap_or_default()
+    }
+
+    pub fn get_de(&self, code: &str) -> Option<&BcDe> {
+        self.de.iter().find(|de| de.project_code == code)
+    }
+
+    pub fn get_rcj(&self, code: &str) -> Option<&BcRcj> {
+        self.rcj.iter().find(|rcj| rcj.material_code == code)
+    }
+
+    /// 按编码新增或覆盖补充定额
+    pub fn upsert_de(&mut self, de: BcDe) {
+        match self
+            .de
+            .iter_mut()
+            .find(|d| d.project_code == de.project_code)
+        {
+            Some(exist) => *exist = de,
+            None => self.de.push(de),
+        }
+    }
+
+    /// 按编码新增或覆盖补充人材机
+    pub fn upsert_rcj(&mut self, rcj: BcRcj) {
+        match self
+            .rcj
+            .iter_mut()
+            .find(|r| r.material_code == rcj.material_code)
+        {
+            Some(exist) => *exist = rcj,
+            None => self.rcj.push(rcj),
+        }
+    }
+
+    /// 合并另一个补充库 已存在的编码以 other 为准
+    pub fn merge(&mut self, other: &BcLibrary) {
+        for de in other.de.iter() {
+            self.upsert_de(de.clone());
+        }
+        for rcj in other.rcj.iter() {
+            self.upsert_rcj(rcj.clone());
+        }
+    }
+
+    /// 生成下一个补充定额编码 补子目1、补子目2 ...
+    pub fn next_de_code(&self) -> String {
+        let max = max_suffix(
+            self.de.iter().map(|de| de.project_code.as_str()),
+            BC_DE_PREFIX,
+        );
+        format!("{}{}", BC_DE_PREFIX, max + 1)
+    }
+
+    /// 生成下一个补充人材机编码 补充材料001、补充人工001 ...
+    pub fn next_rcj_code(&self, r#type: &str) -> String {
+        let prefix = Self::rcj_prefix(r#type);
+        let max = max_suffix(
+            self.rcj.iter().map(|rcj| rcj.material_code.as_str()),
+            prefix,
+        );
+        format!("{}{:03}", prefix, max + 1)
+    }
+
+    fn rcj_prefix(r#type: &str) -> &'static str {
+        match RcjKind::from_type(r#type) {
+            RcjKind::Rg => "补充人工",
+            RcjKind::Cl => "补充材料",
+            RcjKind::Jx => "补充机械",
+            RcjKind::Sb => "补充设备",
+            RcjKind::Zc => "补充主材",
+        }
+    }
+}
+
+/// 取出 前缀 + 数字 形式编码中的最大数字
+fn max_suffix<'a>(codes: impl Iterator<Item = &'a str>, prefix: &str) -> u32 {
+    codes
+        .filter_map(|code| code.strip_prefix(prefix))
+        .filter_map(|suffix| suffix.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+/// 把补充定义写入工程项目 使打开该工程的用户都能看到
+fn save_to_doc(tr: &mut Transaction, root_id: &NodeId, library: &BcLibrary) -> TransformResult<()> {
+    let doc = tr.doc();
+    if doc.get_node(root_id).is_none() {
+        return Err(anyhow::anyhow!("工程项目不存在".to_string()));
+    }
+    let mut current = BcLibrary::from_doc(&doc, root_id);
+    current.merge(library);
+    tr.set_node_attribute(
+        root_id.clone(),
+        HashMap::from([(BC_LIBRARY_ATTR.to_string(), serde_json::to_value(current)?)]).into(),
+    )?;
+    Ok(())
+}
+
+/// 保存补充定义到工程项目
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveBcLibraryCommand {
+    pub editor_name: String,
+    pub library: BcLibrary,
+}
+
+#[async_trait]
+impl Command for SaveBcLibraryCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        save_to_doc(tr, &self.editor_name, &self.library)
+    }
+
+    fn name(&self) -> String {
+        "save_bc_library".to_string()
+    }
+}
+
+/// 插入补充定额 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsertBcDeRequest {
+    pub editor_name: String,
+    /// 目标清单节点
+    pub parent_id: String,
+    /// 补充定额编码
+    pub code: String,
+    pub quantity: Option<Value>,
+}
+
+/// 插入补充定额 与库定额相同 定额节点下按组成生成人材机节点
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsertBcDeCommand {
+    pub data: InsertBcDeRequest,
+    pub de: BcDe,
+    pub id: NodeId,
+}
+
+#[async_trait]
+impl Command for InsertBcDeCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let mut attrs = self.de.to_attrs();
+        if let Some(quantity) = &self.data.quantity {
+            attrs.insert("quantity".to_string(), quantity.clone());
+        }
+        self.add_node(
+            tr,
+            &AddRequest {
+                editor_name: self.data.editor_name.clone(),
+                parent_id: self.data.parent_id.clone(),
+                id: Some(self.id.clone()),
+                r#type: DE_STR.to_string(),
+                attrs: Some(attrs),
+            },
+        )
+        .await?;
+        for rcj in self.de.rcjs.iter() {
+            let mut attrs = rcj.to_attrs();
+            attrs.insert("deId".to_string(), self.id.clone().into());
+            self.add_node(
+                tr,
+                &AddRequest {
+                    editor_name: self.data.editor_name.clone(),
+                    parent_id: self.id.clone(),
+                    id: Some(IdGenerator::get_id()),
+                    r#type: RCJ_STR.to_string(),
+                    attrs: Some(attrs),
+                },
+            )
+            .await?;
+        }
+        let mut library = BcLibrary::default();
+        library.upsert_de(self.de.clone());
+        save_to_doc(tr, &self.data.editor_name, &library)?;
+        //标记 当前 定额 节点 id 用于后续汇总使用
+        tr.set_meta("de_ids", vec![self.id.clone()]);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "insert_bc_de".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for InsertBcDeCommand {}
+
+/// 插入补充人材机 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsertBcRcjRequest {
+    pub editor_name: String,
+    /// 目标定额节点
+    pub parent_id: String,
+    /// 补充人材机编码
+    pub code: String,
+    pub res_qty: Option<Decimal>,
+}
+
+/// 插入补充人材机
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InsertBcRcjCommand {
+    pub data: InsertBcRcjRequest,
+    pub rcj: BcRcj,
+    pub id: NodeId,
+}
+
+#[async_trait]
+impl Command for InsertBcRcjCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let mut rcj = self.rcj.clone();
+        if let Some(res_qty) = self.data.res_qty {
+            rcj.res_qty = res_qty;
+        }
+        let mut attrs = rcj.to_attrs();
+        attrs.insert("deId".to_string(), self.data.parent_id.clone().into());
+        self.add_node(
+            tr,
+            &AddRequest {
+                editor_name: self.data.editor_name.clone(),
+                parent_id: self.data.parent_id.clone(),
+                id: Some(self.id.clone()),
+                r#type: RCJ_STR.to_string(),
+                attrs: Some(attrs),
+            },
+        )
+        .await?;
+        let mut library = BcLibrary::default();
+        library.upsert_rcj(self.rcj.clone());
+        save_to_doc(tr, &self.data.editor_name, &library)?;
+        tr.set_meta("de_ids", vec![self.data.parent_id.clone()]);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "insert_bc_rcj".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for InsertBcRcjCommand {}
@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use mf_model::types::NodeId;
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::{exchange::ImportTreeCommand, DeleteNodeRequest, ShareCommand},
+    exchange::json_tree::{check_tree, JsonTreeCheck, JsonTreeNode},
+    utils::{clipboard::is_self_or_descendant, price::check_children_unlocked},
+};
+
+/// 粘贴 节点已重新生成 id 按目标工程项目的 schema 校验后添加到目标节点下
+/// 同一工程项目内剪切粘贴时 在同一事务中删除来源节点
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasteCommand {
+    pub editor_name: String,
+    pub parent_id: NodeId,
+    pub nodes: Vec<JsonTreeNode>,
+    /// 需要删除的剪切来源节点
+    pub remove_ids: Vec<NodeId>,
+}
+
+#[async_trait]
+impl Command for PasteCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let doc = tr.doc();
+        let parent = match doc.get_node(&self.parent_id) {
+            Some(parent) => parent,
+            None => return Err(anyhow::anyhow!("目标节点不存在".to_string())),
+        };
+        check_children_unlocked(&doc, &self.parent_id).map_err(|e| anyhow::anyhow!(e))?;
+        for id in self.remove_ids.iter() {
+            if is_self_or_descendant(&doc, &self.parent_id, id) {
+                return Err(anyhow::anyhow!("不能粘贴到剪切节点自身或其下级".to_string()));
+            }
+        }
+        let mut nodes = self.nodes.clone();
+        let mut check = JsonTreeCheck::default();
+        for node in nodes.iter_mut() {
+            check_tree(&tr.schema, Some(&parent.r#type), node, "", &mut check);
+        }
+        if let Some(first) = check.errors.first() {
+            return Err(anyhow::anyhow!("{}: {}", first.path, first.message));
+        }
+        let mut de_ids = Vec::new();
+        for node in nodes.iter() {
+            ImportTreeCommand::add_tree(tr, &self.parent_id, node, &mut de_ids)?;
+        }
+        for id in self.remove_ids.iter() {
+            self.delete_node(
+                tr,
+                &DeleteNodeRequest {
+                    editor_name: self.editor_name.clone(),
+                    id: id.clone(),
+                },
+            )
+            .await?;
+        }
+        //粘贴的定额重新汇总
+        tr.set_meta("de_ids", de_ids);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "paste".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for PasteCommand {}
+
+/// 删除节点 跨工程项目剪切粘贴后删除来源工程项目中的节点
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoveNodesCommand {
+    pub editor_name: String,
+    pub ids: Vec<NodeId>,
+}
+
+#[async_trait]
+impl Command for RemoveNodesCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        for id in self.ids.iter() {
+            self.delete_node(
+                tr,
+                &DeleteNodeRequest {
+                    editor_name: self.editor_name.clone(),
+                    id: id.clone(),
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "remove_nodes".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for RemoveNodesCommand {}
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use mf_model::{id_generator::IdGenerator, types::NodeId};
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::{AddRequest, ShareCommand},
+    import::qd::{QdColumnMapping, QdImportNode},
+    nodes::fbfx_csxm::{CSXM_STR, FBFX_STR},
+};
+
+/// 导入清单 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DrqdRequest {
+    pub editor_name: String,
+    /// 目标 分部分项/措施项目 节点
+    pub parent_id: String,
+    /// 本地 XLSX/CSV 文件路径
+    pub file_path: String,
+    pub sheet: Option<String>,
+    /// 列映射 未指定时按表头猜测
+    pub mapping: Option<QdColumnMapping>,
+    /// 只解析不导入 用于预览无法识别的行
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 批量创建导入的 分部、清单 一次事务完成
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DrqdCommand {
+    pub editor_name: String,
+    pub parent_id: NodeId,
+    pub nodes: Vec<QdImportNode>,
+}
+
+impl DrqdCommand {
+    async fn add_tree(
+        &self,
+        tr: &mut Transaction,
+        parent_id: &NodeId,
+        node: &QdImportNode,
+    ) -> TransformResult<()> {
+        let id = IdGenerator::get_id();
+        self.add_node(
+            tr,
+            &AddRequest {
+                editor_name: self.editor_name.clone(),
+                parent_id: parent_id.clone(),
+                id: Some(id.clone()),
+                r#type: node.r#type.clone(),
+                attrs: Some(node.attrs.clone()),
+            },
+        )
+        .await?;
+        for child in node.children.iter() {
+            Box::pin(self.add_tree(tr, &id, child)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for DrqdCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        match tr.doc().get_node(&self.parent_id) {
+            Some(parent) if parent.r#type == FBFX_STR || parent.r#type == CSXM_STR => {}
+            Some(_) => {
+                return Err(anyhow::anyhow!(
+                    "只能导入到 分部分项 或 措施项目 下".to_string()
+                ))
+            }
+            None => return Err(anyhow::anyhow!("目标节点不存在".to_string())),
+        }
+        for node in self.nodes.iter() {
+            self.add_tree(tr, &self.parent_id, node).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "import_qd".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for DrqdCommand {}
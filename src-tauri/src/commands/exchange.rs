@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use mf_model::types::NodeId;
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    commands::ShareCommand,
+    exchange::json_tree::JsonTreeNode,
+    nodes::{fbfx_csxm::DE_STR, field::coerce_attrs},
+    utils::{node::children, price::check_children_unlocked},
+};
+
+/// 删除节点下已有子节点 新建节点时按 schema 自动补全的子节点在导入数据有子节点时移除
+fn clear_children(tr: &mut Transaction, id: &NodeId) -> TransformResult<()> {
+    let ids: Vec<NodeId> = children(&tr.doc(), id)
+        .iter()
+        .map(|child| child.id.clone())
+        .collect();
+    if !ids.is_empty() {
+        tr.remove_node(id.clone(), ids)?;
+    }
+    Ok(())
+}
+
+/// 导入 JSON 树 保留文件中的 id 与标记 调用前需完成 schema 校验及 id 处理
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportTreeCommand {
+    pub editor_name: String,
+    pub parent_id: NodeId,
+    /// 目标节点自身需要更新的属性 导入为新工程项目时写入根节点
+    pub parent_attrs: Option<HashMap<String, Value>>,
+    pub nodes: Vec<JsonTreeNode>,
+    /// 先删除目标节点下已有的子节点
+    pub replace: bool,
+}
+
+impl ImportTreeCommand {
+    /// 按树结构逐层添加节点 保留 id 与标记
+    pub(crate) fn add_tree(
+        tr: &mut Transaction,
+        parent_id: &NodeId,
+        node: &JsonTreeNode,
+        de_ids: &mut Vec<NodeId>,
+    ) -> TransformResult<()> {
+        let node_type = match tr.schema.nodes.get(&node.r#type) {
+            Some(node_type) => node_type.clone(),
+            None => return Err(anyhow::anyhow!("节点类型 {} 不存在", node.r#type)),
+        };
+        let mut attrs = node.attrs_map();
+        coerce_attrs(&tr.doc(), &node.id, &node.r#type, &mut attrs)?;
+        let created = node_type.create_and_fill(
+            Some(node.id.clone()),
+            Some(&attrs),
+            vec![],
+            Some(node.marks.clone()),
+            &tr.schema,
+        );
+        tr.add_node(parent_id.clone(), vec![created])?;
+        if node.r#type == DE_STR {
+            de_ids.push(node.id.clone());
+        }
+        if !node.children.is_empty() {
+            clear_children(tr, &node.id)?;
+        }
+        for child in node.children.iter() {
+            Self::add_tree(tr, &node.id, child, de_ids)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for ImportTreeCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        if tr.doc().get_node(&self.parent_id).is_none() {
+            return Err(anyhow::anyhow!("目标节点不存在".to_string()));
+        }
+        check_children_unlocked(&tr.doc(), &self.parent_id).map_err(|e| anyhow::anyhow!(e))?;
+        if let Some(attrs) = &self.parent_attrs {
+            tr.set_node_attribute(self.parent_id.clone(), attrs.clone().into())?;
+        }
+        if self.replace {
+            clear_children(tr, &self.parent_id)?;
+        }
+        let mut de_ids = Vec::new();
+        for node in self.nodes.iter() {
+            Self::add_tree(tr, &self.parent_id, node, &mut de_ids)?;
+        }
+        //标记导入的定额 用于后续汇总
+        tr.set_meta("de_ids", de_ids);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "import_tree".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for ImportTreeCommand {}
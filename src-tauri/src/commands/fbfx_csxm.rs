@@ -1,9 +1,17 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
+use mf_model::types::NodeId;
 use mf_state::{transaction::Command, Transaction};
 use mf_transform::TransformResult;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::commands::{AddRequest, DeleteNodeRequest, ShareCommand};
+use crate::{
+    commands::{AddRequest, DeleteNodeRequest, ShareCommand, UpdateAttrsRequest},
+    nodes::fbfx_csxm::{DE_STR, QD_STR},
+    utils::price::{check_unlocked, de_unit_cost, qd_unit_cost_with, PriceOptions},
+};
 
 // 插入分部分项
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,3 +53,141 @@ impl Command for DeleteFbfxCsxmCommand {
 
 #[async_trait]
 impl ShareCommand for DeleteFbfxCsxmCommand {}
+
+/// 更新 分部分项 措施项目 行属性(含定额下人材机)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateFbfxCsxmCommand {
+    pub data: UpdateAttrsRequest,
+}
+
+#[async_trait]
+impl Command for UpdateFbfxCsxmCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        tr.set_meta("update_fbfx_csxm", self.data.clone());
+        self.update_attrs(tr, &self.data).await
+    }
+
+    fn name(&self) -> String {
+        "update_fbfx_csxm".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for UpdateFbfxCsxmCommand {}
+
+/// 批量操作
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperation {
+    Add(AddRequest),
+    Update(UpdateAttrsRequest),
+    Delete(DeleteNodeRequest),
+}
+
+/// 批量操作 请求 按顺序执行 后面的操作可引用前面新增的节点 id
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchRequest {
+    pub editor_name: String,
+    pub operations: Vec<BatchOperation>,
+}
+
+/// 批量 新增、修改、删除 分部分项 措施项目 行
+/// 在同一事务中执行 任一操作失败则整体失败 只产生一条历史记录 价格只汇总一次
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchFbfxCsxmCommand {
+    pub data: BatchRequest,
+}
+
+#[async_trait]
+impl Command for BatchFbfxCsxmCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let mut de_ids = Vec::new();
+        for (index, operation) in self.data.operations.iter().enumerate() {
+            let result = match operation {
+                BatchOperation::Add(data) => {
+                    if let Some(id) = data.id.as_ref() {
+                        if tr.doc().get_node(id).is_some() {
+                            return Err(anyhow::anyhow!(
+                                "第 {} 个操作: 节点 {} 已存在",
+                                index + 1,
+                                id
+                            ));
+                        }
+                        if data.r#type == DE_STR {
+                            de_ids.push(id.clone());
+                        }
+                    }
+                    self.add_node(tr, data).await
+                }
+                BatchOperation::Update(data) => self.update_attrs(tr, data).await,
+                BatchOperation::Delete(data) => self.delete_node(tr, data).await,
+            };
+            result.map_err(|e| anyhow::anyhow!("第 {} 个操作: {}", index + 1, e))?;
+        }
+        //新增的定额 重新汇总 修改的行由属性变更触发汇总 已删除的节点在汇总时忽略
+        tr.set_meta("de_ids", de_ids);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "batch_fbfx_csxm".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for BatchFbfxCsxmCommand {}
+
+/// 锁定/解锁综合单价 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LockPriceRequest {
+    pub editor_name: String,
+    /// 清单、定额 节点
+    pub ids: Vec<NodeId>,
+    pub lock: bool,
+}
+
+/// 锁定/解锁综合单价
+/// 锁定时保存当前单价构成快照 后续计算使用快照值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LockPriceCommand {
+    pub data: LockPriceRequest,
+}
+
+#[async_trait]
+impl Command for LockPriceCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let doc = tr.doc();
+        for id in self.data.ids.iter() {
+            let node = match doc.get_node(id) {
+                Some(node) => node,
+                None => return Err(anyhow::anyhow!("目标节点不存在".to_string())),
+            };
+            check_unlocked(&doc, id).map_err(|e| anyhow::anyhow!(e))?;
+            let attrs = if self.data.lock {
+                let cost = match node.r#type.as_str() {
+                    QD_STR => qd_unit_cost_with(&doc, id, &PriceOptions::of(&doc, id)),
+                    DE_STR => de_unit_cost(&doc, id).0,
+                    _ => return Err(anyhow::anyhow!("只能锁定清单、定额".to_string())),
+                };
+                HashMap::from([
+                    ("lockPrice".to_string(), Value::from(true)),
+                    ("lockedCost".to_string(), serde_json::to_value(cost)?),
+                ])
+            } else {
+                HashMap::from([
+                    ("lockPrice".to_string(), Value::from(false)),
+                    ("lockedCost".to_string(), Value::Null),
+                ])
+            };
+            tr.set_node_attribute(id.clone(), attrs.into())?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "lock_price".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for LockPriceCommand {}
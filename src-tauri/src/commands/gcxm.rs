@@ -7,7 +7,10 @@ use mf_transform::TransformResult;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    commands::{AddMarkRequest, AddRequest, DeleteNodeRequest, ShareCommand},
+    commands::{
+        AddMarkRequest, AddRequest, DeleteNodeRequest, MoveRequest, ShareCommand,
+        UpdateAttrsRequest,
+    },
     marks::FOOTNOTE_STR,
 };
 #[derive(Debug, Clone)]
@@ -80,3 +83,41 @@ impl Command for DeleteGcxmCammand {
 
 #[async_trait]
 impl ShareCommand for DeleteGcxmCammand {}
+
+/// 更新 工程项目、单项、单位 属性(含单位工程计价设置)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateGcxmAttrsCammand {
+    pub data: UpdateAttrsRequest,
+}
+
+#[async_trait]
+impl Command for UpdateGcxmAttrsCammand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        self.update_attrs(tr, &self.data).await
+    }
+    fn name(&self) -> String {
+        "update_gcxm_attrs".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for UpdateGcxmAttrsCammand {}
+
+/// 移动节点 上移、下移、移动到指定位置或其他父节点下
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MoveNodeCommand {
+    pub data: MoveRequest,
+}
+
+#[async_trait]
+impl Command for MoveNodeCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        self.move_node(tr, &self.data).await
+    }
+    fn name(&self) -> String {
+        "move_node".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for MoveNodeCommand {}
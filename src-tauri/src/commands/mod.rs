@@ -7,14 +7,32 @@ use mf_transform::TransformResult;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{
+    exchange::json_tree::content_allows,
+    nodes::{field::coerce_attrs, gcxm::CUSTOM_COLUMNS_ATTR},
+    utils::{
+        clipboard::is_self_or_descendant,
+        price::{check_children_unlocked, check_unlocked},
+    },
+};
+
+pub mod bc;
+pub mod clipboard;
 pub mod djgc;
+pub mod drqd;
+pub mod exchange;
 pub mod fbfx_csxm;
 pub mod gcxm;
 pub mod rcj;
+pub mod tj;
+pub mod xmbm;
+pub mod zjfa;
 
 /// 添加节点 请求
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AddRequest {
+    /// 批量操作中可省略 以批量请求的 editor_name 为准
+    #[serde(default)]
     pub editor_name: String,
     pub parent_id: String,
     pub id: Option<NodeId>,
@@ -23,12 +41,16 @@ pub struct AddRequest {
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeleteNodeRequest {
+    /// 批量操作中可省略 以批量请求的 editor_name 为准
+    #[serde(default)]
     pub editor_name: String,
     pub id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UpdateAttrsRequest {
+    /// 批量操作中可省略 以批量请求的 editor_name 为准
+    #[serde(default)]
     pub editor_name: String,
     pub id: String,
     pub attrs: HashMap<String, Value>,
@@ -48,6 +70,31 @@ pub struct RemoveMarkRequest {
     pub marks: Vec<String>,
 }
 
+/// 移动目标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MoveTarget {
+    /// 上移一行
+    Up,
+    /// 下移一行
+    Down,
+    /// 移动到同级的指定位置
+    Index { index: usize },
+    /// 移动到其他父节点下 index 为空时放在最后
+    Parent {
+        parent_id: NodeId,
+        index: Option<usize>,
+    },
+}
+
+/// 移动节点 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MoveRequest {
+    pub editor_name: String,
+    pub id: NodeId,
+    pub target: MoveTarget,
+}
+
 #[async_trait]
 pub trait ShareCommand: Command {
     /// 添加节点
@@ -55,10 +102,18 @@ pub trait ShareCommand: Command {
         if tr.doc().get_node(&data.parent_id.to_string()).is_none() {
             return Err(anyhow::anyhow!("目标节点不存在".to_string()));
         }
+        check_children_unlocked(&tr.doc(), &data.parent_id).map_err(|e| anyhow::anyhow!(e))?;
+        let mut attrs = data.attrs.clone().unwrap_or_default();
+        coerce_attrs(
+            &tr.doc(),
+            &data.id.clone().unwrap_or_default(),
+            &data.r#type,
+            &mut attrs,
+        )?;
         if let Some(node_type) = tr.schema.nodes.get(&data.r#type) {
             let nodes = node_type.create_and_fill(
                 data.id.clone(),
-                Some(&data.attrs.clone().unwrap_or_default()),
+                Some(&attrs),
                 vec![],
                 None,
                 &tr.schema,
@@ -80,10 +135,85 @@ pub trait ShareCommand: Command {
         if tr.doc().get_node(&data.id.to_string()).is_none() {
             return Err(anyhow::anyhow!("目标节点不存在".to_string()));
         }
+        check_unlocked(&tr.doc(), &data.id).map_err(|e| anyhow::anyhow!(e))?;
         let parent_id = tr.doc().get_parent_node(&data.id).unwrap().id.clone();
         tr.remove_node(parent_id, vec![data.id.clone()])?;
         Ok(())
     }
+    /// 移动节点 同级调整顺序或移动到其他父节点下
+    async fn move_node(&self, tr: &mut Transaction, data: &MoveRequest) -> TransformResult<()> {
+        let doc = tr.doc();
+        let node = match doc.get_node(&data.id) {
+            Some(node) => node,
+            None => return Err(anyhow::anyhow!("目标节点不存在".to_string())),
+        };
+        let parent = match doc.get_parent_node(&data.id) {
+            Some(parent) => parent,
+            None => return Err(anyhow::anyhow!("根节点不能移动".to_string())),
+        };
+        let current = parent
+            .content
+            .iter()
+            .position(|id| id == &data.id)
+            .unwrap_or_default();
+        let (target_parent, index) = match &data.target {
+            MoveTarget::Up => {
+                if current == 0 {
+                    return Err(anyhow::anyhow!("已经是第一行".to_string()));
+                }
+                (parent.clone(), Some(current - 1))
+            }
+            MoveTarget::Down => {
+                if current + 1 >= parent.content.len() {
+                    return Err(anyhow::anyhow!("已经是最后一行".to_string()));
+                }
+                (parent.clone(), Some(current + 1))
+            }
+            MoveTarget::Index { index } => (parent.clone(), Some(*index)),
+            MoveTarget::Parent { parent_id, index } => match doc.get_node(parent_id) {
+                Some(target) => (target, *index),
+                None => return Err(anyhow::anyhow!("目标父节点不存在".to_string())),
+            },
+        };
+        // 移动后的位置以移出本节点后的子节点列表计算
+        let len = if target_parent.id == parent.id {
+            parent.content.len() - 1
+        } else {
+            target_parent.content.len()
+        };
+        if let Some(index) = index {
+            if index > len {
+                return Err(anyhow::anyhow!("目标位置超出范围".to_string()));
+            }
+        }
+        if target_parent.id != parent.id {
+            if is_self_or_descendant(&doc, &target_parent.id, &data.id) {
+                return Err(anyhow::anyhow!("不能移动到自身或其下级".to_string()));
+            }
+            let content = tr
+                .schema
+                .nodes
+                .get(&target_parent.r#type)
+                .and_then(|node_type| node_type.spec.content.clone())
+                .unwrap_or_default();
+            if !content_allows(&tr.schema, &content, &node.r#type) {
+                return Err(anyhow::anyhow!(
+                    "{} 下不能包含 {}",
+                    target_parent.r#type,
+                    node.r#type
+                ));
+            }
+            check_children_unlocked(&doc, &target_parent.id).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        check_children_unlocked(&doc, &parent.id).map_err(|e| anyhow::anyhow!(e))?;
+        tr.move_node(
+            parent.id.clone(),
+            target_parent.id.clone(),
+            data.id.clone(),
+            index,
+        )?;
+        Ok(())
+    }
     /// 更新节点属性
     async fn update_attrs(
         &self,
@@ -93,7 +223,15 @@ pub trait ShareCommand: Command {
         if tr.doc().get_node(&data.id.to_string()).is_none() {
             return Err(anyhow::anyhow!("目标节点不存在".to_string()));
         }
-        tr.set_node_attribute(data.id.to_string(), data.attrs.clone().into())?;
+        // 自定义列在新建工程项目时确定 创建编辑器时已合并到属性定义 之后不能修改
+        if data.attrs.contains_key(CUSTOM_COLUMNS_ATTR) {
+            return Err(anyhow::anyhow!("自定义列只能在新建工程项目时设置".to_string()));
+        }
+        check_unlocked(&tr.doc(), &data.id).map_err(|e| anyhow::anyhow!(e))?;
+        let node_type = tr.doc().get_node(&data.id).unwrap().r#type.to_string();
+        let mut attrs = data.attrs.clone();
+        coerce_attrs(&tr.doc(), &data.id, &node_type, &mut attrs)?;
+        tr.set_node_attribute(data.id.to_string(), attrs.into())?;
         Ok(())
     }
     /// 添加标记
@@ -0,0 +1,233 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use mf_model::{node_pool::NodePool, types::NodeId};
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    commands::ShareCommand,
+    nodes::{
+        djgc::{DJGC_ROW_STR, DJGC_STR},
+        fbfx_csxm::{DE_STR, QD_STR},
+        rcj::RCJ_STR,
+    },
+    utils::{
+        fyhz::gczj_total,
+        money::{decimal_value, get_decimal, round, Decimal, CALC_PRECISION},
+        node::{children_of_type, descendants_of_type, get_str},
+        price::{is_jgcl, is_locked, is_zgcl, rcj_price, RcjKind},
+    },
+};
+
+/// 目标造价 允许误差 0.01
+const TJ_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+/// 目标造价 最大迭代次数
+const TJ_MAX_ITERATIONS: usize = 20;
+
+/// 调价方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TjMode {
+    /// 按 人工/材料/机械 系数调整人材机单价
+    Rcj,
+    /// 按系数调整综合单价 所有人材机及固定金额的单价构成行同比例调整
+    Zhdj,
+}
+
+/// 调价 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TjRequest {
+    pub editor_name: String,
+    /// 单位工程 或 工程项目 id
+    pub id: String,
+    pub mode: TjMode,
+    /// 人工系数
+    pub rg: Option<Decimal>,
+    /// 材料系数
+    pub cl: Option<Decimal>,
+    /// 机械系数
+    pub jx: Option<Decimal>,
+    /// 综合单价系数
+    pub zhdj: Option<Decimal>,
+    /// 目标造价 设置后在上述系数基础上迭代求解统一的调整比例
+    pub target: Option<Decimal>,
+}
+
+impl TjRequest {
+    /// 指定人材机的调整系数 None 表示不参与调价
+    fn coefficient(&self, kind: RcjKind) -> Option<Decimal> {
+        match self.mode {
+            TjMode::Zhdj => Some(self.zhdj.unwrap_or(Decimal::ONE)),
+            TjMode::Rcj => match kind {
+                RcjKind::Rg => Some(self.rg.unwrap_or(Decimal::ONE)),
+                RcjKind::Cl => Some(self.cl.unwrap_or(Decimal::ONE)),
+                RcjKind::Jx => Some(self.jx.unwrap_or(Decimal::ONE)),
+                RcjKind::Sb | RcjKind::Zc => None,
+            },
+        }
+    }
+}
+
+/// 参与调价的价格项
+#[derive(Debug, Clone)]
+struct TjItem {
+    id: NodeId,
+    key: &'static str,
+    price: Decimal,
+    coefficient: Decimal,
+}
+
+/// 收集节点下参与调价的价格项
+/// 锁定综合单价的清单、定额 以及 暂估材料、甲供材料 不参与调价
+fn collect_items(doc: &NodePool, request: &TjRequest) -> Vec<TjItem> {
+    let mut items = Vec::new();
+    for qd in descendants_of_type(doc, &request.id, &[QD_STR]) {
+        if is_locked(&qd) {
+            continue;
+        }
+        for de in children_of_type(doc, &qd.id, DE_STR) {
+            if is_locked(&de) {
+                continue;
+            }
+            for rcj in children_of_type(doc, &de.id, RCJ_STR) {
+                if is_zgcl(&rcj) || is_jgcl(&rcj) {
+                    continue;
+                }
+                if let Some(coefficient) = request.coefficient(RcjKind::of(&rcj)) {
+                    items.push(TjItem {
+                        id: rcj.id.clone(),
+                        key: "priceMarket",
+                        price: rcj_price(&rcj),
+                        coefficient,
+                    });
+                }
+            }
+            if request.mode != TjMode::Zhdj {
+                continue;
+            }
+            for djgc in children_of_type(doc, &de.id, DJGC_STR) {
+                for row in children_of_type(doc, &djgc.id, DJGC_ROW_STR) {
+                    if get_str(&row, "caculateBase").trim().is_empty() {
+                        items.push(TjItem {
+                            id: row.id.clone(),
+                            key: "price",
+                            price: get_decimal(&row, "price"),
+                            coefficient: request.zhdj.unwrap_or(Decimal::ONE),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    items
+}
+
+impl TjItem {
+    /// 原价 × 系数 × 调整比例
+    /// 保留中间精度 综合单价、合价由汇总时按单位工程的取整规则计算
+    fn attrs(&self, ratio: Decimal) -> HashMap<String, Value> {
+        let price = round(self.price * self.coefficient * ratio, CALC_PRECISION);
+        HashMap::from([(self.key.to_string(), decimal_value(price.normalize()))])
+    }
+}
+
+/// 写入调整后的价格
+fn apply_items(tr: &mut Transaction, items: &[TjItem], ratio: Decimal) -> TransformResult<()> {
+    for item in items.iter() {
+        tr.set_node_attribute(item.id.clone(), item.attrs(ratio).into())?;
+    }
+    Ok(())
+}
+
+/// 按调整比例试算工程造价 在文档副本上修改价格 不产生事务步骤
+fn total_at(
+    doc: &NodePool,
+    items: &[TjItem],
+    id: &NodeId,
+    ratio: Decimal,
+) -> anyhow::Result<Decimal> {
+    let mut tree = doc.get_inner().as_ref().clone();
+    for item in items.iter() {
+        tree.update_attr(&item.id, item.attrs(ratio).into())?;
+    }
+    let doc = NodePool::new(Arc::new(tree));
+    Ok(gczj_total(&doc, id))
+}
+
+/// 调价 单位工程 或 整个工程项目
+/// 直接按系数调整 或 以割线法迭代求解调整比例使工程造价达到目标值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TjCommand {
+    pub data: TjRequest,
+}
+
+#[async_trait]
+impl Command for TjCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let doc = tr.doc();
+        if doc.get_node(&self.data.id).is_none() {
+            return Err(anyhow::anyhow!("目标节点不存在".to_string()));
+        }
+        let items = collect_items(&doc, &self.data);
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("没有可调价的人材机".to_string()));
+        }
+        let target = match self.data.target {
+            Some(target) => target,
+            None => return apply_items(tr, &items, Decimal::ONE),
+        };
+        let ratio = solve_ratio(&doc, &items, &self.data.id, target)?;
+        apply_items(tr, &items, ratio)
+    }
+
+    fn name(&self) -> String {
+        "tj".to_string()
+    }
+}
+
+/// 割线法求解 调整比例 ratio 使 造价(ratio) = 目标造价
+/// 造价除取整外与 ratio 成线性关系 通常两三次即收敛
+/// 以 ratio = 0(仅不可调价部分) 和 ratio = 1(按系数调整) 作为初始点
+/// 调整比例保留 CALC_PRECISION 位小数 比例不再变化时停止迭代
+fn solve_ratio(
+    doc: &NodePool,
+    items: &[TjItem],
+    id: &NodeId,
+    target: Decimal,
+) -> anyhow::Result<Decimal> {
+    let mut prev = (Decimal::ZERO, total_at(doc, items, id, Decimal::ZERO)?);
+    let mut current = (Decimal::ONE, total_at(doc, items, id, Decimal::ONE)?);
+    for _ in 0..TJ_MAX_ITERATIONS {
+        if (current.1 - target).abs() <= TJ_TOLERANCE {
+            return Ok(current.0);
+        }
+        if current.0 == prev.0 {
+            break;
+        }
+        let slope = (current.1 - prev.1) / (current.0 - prev.0);
+        if slope.is_zero() {
+            return Err(anyhow::anyhow!(
+                "可调价部分金额为0 无法达到目标造价".to_string()
+            ));
+        }
+        let ratio = round(current.0 + (target - current.1) / slope, CALC_PRECISION).normalize();
+        if ratio <= Decimal::ZERO {
+            return Err(anyhow::anyhow!(
+                "目标造价低于不可调价部分金额 无法达到".to_string()
+            ));
+        }
+        prev = current;
+        current = (ratio, total_at(doc, items, id, ratio)?);
+    }
+    if (current.1 - target).abs() <= TJ_TOLERANCE {
+        Ok(current.0)
+    } else {
+        Err(anyhow::anyhow!("调价未能收敛到目标造价".to_string()))
+    }
+}
+
+#[async_trait]
+impl ShareCommand for TjCommand {}
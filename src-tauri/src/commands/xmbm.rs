@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use mf_model::types::NodeId;
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    commands::ShareCommand,
+    utils::xmbm::{dwgc_ids, renumber, CodeMode, CODE_ATTR},
+};
+
+/// 项目编码事务标记 避免编码结果再次触发自动编码
+pub const XMBM_META: &str = "xmbm";
+
+/// 重排项目编码 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RenumberRequest {
+    pub editor_name: String,
+    /// 单位工程 或 单项工程、工程项目(其下全部单位工程)
+    pub id: NodeId,
+    /// 为空时重排全部编码
+    pub mode: Option<CodeMode>,
+}
+
+/// 写入项目编码
+pub fn apply_codes(tr: &mut Transaction, codes: Vec<(NodeId, String)>) -> TransformResult<()> {
+    for (id, code) in codes {
+        tr.set_node_attribute(
+            id,
+            HashMap::from([(CODE_ATTR.to_string(), Value::from(code))]).into(),
+        )?;
+    }
+    Ok(())
+}
+
+/// 按树顺序重排 分部、清单 项目编码
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RenumberCommand {
+    pub data: RenumberRequest,
+}
+
+#[async_trait]
+impl Command for RenumberCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let doc = tr.doc();
+        let ids = dwgc_ids(&doc, &self.data.id);
+        if ids.is_empty() {
+            return Err(anyhow::anyhow!("单位工程不存在".to_string()));
+        }
+        let mode = self.data.mode.unwrap_or(CodeMode::Auto);
+        for id in ids.iter() {
+            let codes = renumber(&doc, id, mode).map_err(|e| anyhow::anyhow!(e))?;
+            apply_codes(tr, codes)?;
+        }
+        tr.set_meta(XMBM_META, true);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "renumber".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for RenumberCommand {}
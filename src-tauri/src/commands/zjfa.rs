@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use mf_model::{id_generator::IdGenerator, node_pool::NodePool, types::NodeId};
+use mf_state::{transaction::Command, Transaction};
+use mf_transform::TransformResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    commands::{AddRequest, DeleteNodeRequest, ShareCommand},
+    nodes::fbfx_csxm::{DE_STR, QD_STR},
+    utils::{
+        money::{decimal_value, get_decimal, round, Decimal, CALC_PRECISION},
+        node::{attrs_map, children, children_of_type, get_str},
+        price::PriceOptions,
+    },
+};
+
+/// 保存方案时不保留的属性 工程量与合价在应用时按目标清单重新生成
+const SKIP_ATTRS: [&str; 6] = [
+    "quantity",
+    "quantityExpression",
+    "sbfTotal",
+    "zgfTotal",
+    "zjfTotal",
+    "deId",
+];
+
+/// 方案中的子树节点(定额下的人材机、单价构成等)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ZjfaNode {
+    pub r#type: String,
+    pub attrs: HashMap<String, Value>,
+    #[serde(default)]
+    pub children: Vec<ZjfaNode>,
+}
+
+impl ZjfaNode {
+    fn from_doc(doc: &NodePool, id: &NodeId) -> Option<Self> {
+        let node = doc.get_node(id)?;
+        Some(ZjfaNode {
+            r#type: node.r#type.to_string(),
+            attrs: attrs_map(&node)
+                .into_iter()
+                .filter(|(k, _)| !SKIP_ATTRS.contains(&k.as_str()))
+                .collect(),
+            children: children(doc, id)
+                .iter()
+                .filter_map(|child| ZjfaNode::from_doc(doc, &child.id))
+                .collect(),
+        })
+    }
+}
+
+/// 方案中的定额
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ZjfaDe {
+    /// 含量 = 定额工程量 / 清单工程量 应用时按目标清单工程量换算
+    pub ratio: Decimal,
+    pub node: ZjfaNode,
+}
+
+/// 组价方案
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ZjfaScheme {
+    pub id: String,
+    pub name: String,
+    /// 来源清单的项目编码 用于智能匹配
+    pub project_code: String,
+    pub project_name: String,
+    /// 来源清单的项目特征 用于智能匹配
+    pub project_attr: String,
+    pub unit: String,
+    pub des: Vec<ZjfaDe>,
+}
+
+impl ZjfaScheme {
+    /// 从清单构建组价方案 保存定额及其下人材机 不保存工程量
+    pub fn from_qd(doc: &NodePool, qd_id: &NodeId, name: String) -> anyhow::Result<Self> {
+        let qd = doc
+            .get_node(qd_id)
+            .ok_or_else(|| anyhow::anyhow!("清单节点不存在"))?;
+        if qd.r#type != QD_STR {
+            return Err(anyhow::anyhow!("只能从清单保存组价方案"));
+        }
+        let qd_quantity = get_decimal(&qd, "quantity");
+        let des = children_of_type(doc, qd_id, DE_STR)
+            .iter()
+            .filter_map(|de| {
+                let ratio = if qd_quantity.is_zero() {
+                    Decimal::ONE
+                } else {
+                    round(get_decimal(de, "quantity") / qd_quantity, CALC_PRECISION).normalize()
+                };
+                ZjfaNode::from_doc(doc, &de.id).map(|node| ZjfaDe { ratio, node })
+            })
+            .collect::<Vec<_>>();
+        if des.is_empty() {
+            return Err(anyhow::anyhow!("清单下没有定额 无法保存组价方案"));
+        }
+        Ok(ZjfaScheme {
+            id: IdGenerator::get_id(),
+            name,
+            project_code: get_str(&qd, "projectCode"),
+            project_name: get_str(&qd, "projectName"),
+            project_attr: get_str(&qd, "projectAttr"),
+            unit: get_str(&qd, "unit"),
+            des,
+        })
+    }
+
+    /// 与清单的匹配度 0~1
+    /// 项目编码前 9 位(清单基础编码)相同权重最高 其次按公共前缀长度 项目特征按字符二元组相似度
+    pub fn match_score(&self, project_code: &str, project_attr: &str) -> f64 {
+        let prefix = common_prefix_len(&self.project_code, project_code);
+        let code_score = if prefix >= 9 {
+            1.0
+        } else {
+            prefix as f64 / 9.0
+        };
+        let attr_score = bigram_similarity(&self.project_attr, project_attr);
+        code_score * 0.6 + attr_score * 0.4
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count()
+}
+
+/// 字符二元组 Jaccard 相似度 忽略空白与标点
+fn bigram_similarity(a: &str, b: &str) -> f64 {
+    fn bigrams(s: &str) -> HashSet<(char, char)> {
+        let chars: Vec<char> = s.chars().filter(|c| c.is_alphanumeric()).collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    let inter = a.intersection(&b).count() as f64;
+    let union = a.union(&b).count() as f64;
+    inter / union
+}
+
+/// 本地组价方案库
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ZjfaLibrary {
+    pub schemes: Vec<ZjfaScheme>,
+}
+
+impl ZjfaLibrary {
+    /// 本地库文件名
+    pub const LOCAL_NAME: &'static str = "zjfa";
+
+    pub fn get(&self, id: &str) -> Option<&ZjfaScheme> {
+        self.schemes.iter().find(|s| s.id == id)
+    }
+}
+
+/// 应用组价方案 请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApplyZjfaRequest {
+    pub editor_name: String,
+    pub scheme_id: String,
+    /// 目标清单
+    pub qd_ids: Vec<String>,
+    /// 是否先删除目标清单下已有的定额
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// 应用组价方案到清单 重新生成节点 id 并按目标清单工程量换算定额工程量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApplyZjfaCommand {
+    pub data: ApplyZjfaRequest,
+    pub scheme: ZjfaScheme,
+}
+
+impl ApplyZjfaCommand {
+    async fn add_tree(
+        &self,
+        tr: &mut Transaction,
+        parent_id: &NodeId,
+        node: &ZjfaNode,
+        mut attrs: HashMap<String, Value>,
+    ) -> TransformResult<NodeId> {
+        let id = IdGenerator::get_id();
+        self.add_node(
+            tr,
+            &AddRequest {
+                editor_name: self.data.editor_name.clone(),
+                parent_id: parent_id.clone(),
+                id: Some(id.clone()),
+                r#type: node.r#type.clone(),
+                attrs: Some({
+                    attrs.extend(node.attrs.clone());
+                    attrs
+                }),
+            },
+        )
+        .await?;
+        for child in node.children.iter() {
+            let mut child_attrs = HashMap::new();
+            if node.r#type == DE_STR {
+                child_attrs.insert("deId".to_string(), id.clone().into());
+            }
+            Box::pin(self.add_tree(tr, &id, child, child_attrs)).await?;
+        }
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl Command for ApplyZjfaCommand {
+    async fn execute(&self, tr: &mut Transaction) -> TransformResult<()> {
+        let mut de_ids = Vec::new();
+        for qd_id in self.data.qd_ids.iter() {
+            let qd = match tr.doc().get_node(qd_id) {
+                Some(qd) if qd.r#type == QD_STR => qd,
+                _ => return Err(anyhow::anyhow!("目标清单不存在: {}", qd_id)),
+            };
+            if self.data.replace {
+                for de in children_of_type(&tr.doc(), qd_id, DE_STR) {
+                    self.delete_node(
+                        tr,
+                        &DeleteNodeRequest {
+                            editor_name: self.data.editor_name.clone(),
+                            id: de.id.clone(),
+                        },
+                    )
+                    .await?;
+                }
+            }
+            let qd_quantity = get_decimal(&qd, "quantity");
+            let rounding = PriceOptions::of(&tr.doc(), qd_id).rounding;
+            for de in self.scheme.des.iter() {
+                let unit = match de.node.attrs.get("unit") {
+                    Some(Value::String(unit)) => unit.as_str(),
+                    _ => "",
+                };
+                let quantity = rounding.quantity(unit, de.ratio * qd_quantity);
+                let attrs = HashMap::from([("quantity".to_string(), decimal_value(quantity))]);
+                de_ids.push(self.add_tree(tr, qd_id, &de.node, attrs).await?);
+            }
+        }
+        //标记 新增的 定额 节点 id 用于后续汇总使用
+        tr.set_meta("de_ids", de_ids);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        "apply_zjfa".to_string()
+    }
+}
+
+#[async_trait]
+impl ShareCommand for ApplyZjfaCommand {}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::Path,
+    routing::{get, post},
+    Json, Router,
+};
+use mf_model::id_generator::IdGenerator;
+use serde::Deserialize;
+
+use crate::{
+    commands::bc::{
+        BcDe, BcLibrary, BcRcj, InsertBcDeCommand, InsertBcDeRequest, InsertBcRcjCommand,
+        InsertBcRcjRequest, SaveBcLibraryCommand,
+    },
+    error::AppError,
+    res,
+    response::Res,
+    utils::local_library::{load_library, save_library},
+    ContextHelper, ResponseResult,
+};
+
+lazy_static! {
+    /// 本地补充库 读改写 互斥锁
+    static ref BC_LOCAL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// 获取本地补充库与工程项目补充库的合集 工程项目中的定义优先
+async fn merged_library(editor_name: &str) -> Result<BcLibrary, AppError> {
+    let editor = ContextHelper::get_editor(editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let mut library: BcLibrary = load_library(BcLibrary::LOCAL_NAME)?;
+    library.merge(&BcLibrary::from_doc(&doc, &editor_name.to_string()));
+    Ok(library)
+}
+
+/// 把补充定义写入工程项目与本地库
+/// 工程项目写入成功后再保存本地库 事务被拒绝时本地库保持不变
+async fn save_library_both(
+    editor_name: &str,
+    library: BcLibrary,
+    description: String,
+    meta: serde_json::Value,
+) -> Result<(), AppError> {
+    let editor = ContextHelper::get_editor(editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    editor
+        .command_with_meta(
+            Arc::new(SaveBcLibraryCommand {
+                editor_name: editor_name.to_string(),
+                library: library.clone(),
+            }),
+            description,
+            meta,
+        )
+        .await?;
+    let mut local: BcLibrary = load_library(BcLibrary::LOCAL_NAME)?;
+    local.merge(&library);
+    save_library(BcLibrary::LOCAL_NAME, &local)?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBcDePost {
+    pub editor_name: String,
+    pub de: BcDe,
+}
+
+/// 新建补充定额 编码为空时自动生成
+pub async fn create_bc_de(Json(param): Json<CreateBcDePost>) -> ResponseResult<BcDe> {
+    let _guard = BC_LOCAL_LOCK.lock().await;
+    let merged = merged_library(&param.editor_name).await?;
+    let mut de = param.de;
+    if de.project_code.trim().is_empty() {
+        de.project_code = merged.next_de_code();
+    }
+    // 定额组成中未编码的补充人材机同样自动编码
+    let mut codes = merged.clone();
+    for rcj in de.rcjs.iter_mut() {
+        if rcj.material_code.trim().is_empty() {
+            rcj.material_code = codes.next_rcj_code(&rcj.r#type);
+            codes.upsert_rcj(rcj.clone());
+        }
+    }
+    let mut library = BcLibrary::default();
+    for rcj in de.rcjs.iter() {
+        if merged.get_rcj(&rcj.material_code).is_none() {
+            library.upsert_rcj(rcj.clone());
+        }
+    }
+    library.upsert_de(de.clone());
+    save_library_both(
+        &param.editor_name,
+        library,
+        "新建补充定额 {{projectCode}}".to_string(),
+        serde_json::to_value(de.clone())?,
+    )
+    .await?;
+    res!(de)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBcRcjPost {
+    pub editor_name: String,
+    pub rcj: BcRcj,
+}
+
+/// 新建补充人材机 编码为空时自动生成
+pub async fn create_bc_rcj(Json(param): Json<CreateBcRcjPost>) -> ResponseResult<BcRcj> {
+    let _guard = BC_LOCAL_LOCK.lock().await;
+    let merged = merged_library(&param.editor_name).await?;
+    let mut rcj = param.rcj;
+    if rcj.material_code.trim().is_empty() {
+        rcj.material_code = merged.next_rcj_code(&rcj.r#type);
+    }
+    let mut library = BcLibrary::default();
+    library.upsert_rcj(rcj.clone());
+    save_library_both(
+        &param.editor_name,
+        library,
+        "新建补充人材机 {{materialCode}}".to_string(),
+        serde_json::to_value(rcj.clone())?,
+    )
+    .await?;
+    res!(rcj)
+}
+
+/// 获取补充库(本地库 + 工程项目)
+pub async fn get_bc_library(Path(editor_name): Path<String>) -> ResponseResult<BcLibrary> {
+    res!(merged_library(&editor_name).await?)
+}
+
+/// 把工程项目中的补充定义导入本地库 打开同事的工程后使用
+pub async fn import_bc_to_local(Path(editor_name): Path<String>) -> ResponseResult<BcLibrary> {
+    let _guard = BC_LOCAL_LOCK.lock().await;
+    let editor = ContextHelper::get_editor(&editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let doc = editor.unwrap().doc().await;
+    let mut local: BcLibrary = load_library(BcLibrary::LOCAL_NAME)?;
+    local.merge(&BcLibrary::from_doc(&doc, &editor_name));
+    save_library(BcLibrary::LOCAL_NAME, &local)?;
+    res!(local)
+}
+
+/// 插入补充定额
+pub async fn insert_bc_de(Json(param): Json<InsertBcDeRequest>) -> ResponseResult<String> {
+    let library = merged_library(&param.editor_name).await?;
+    let de = library.get_de(&param.code);
+    if de.is_none() {
+        return Err(AppError(anyhow::anyhow!("补充定额不存在".to_string())));
+    }
+    let id = IdGenerator::get_id();
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(InsertBcDeCommand {
+                data: param.clone(),
+                de: de.unwrap().clone(),
+                id: id.clone(),
+            }),
+            "插入补充定额 {{code}}".to_string(),
+            meta,
+        )
+        .await?;
+    res!(id)
+}
+
+/// 插入补充人材机
+pub async fn insert_bc_rcj(Json(param): Json<InsertBcRcjRequest>) -> ResponseResult<String> {
+    let library = merged_library(&param.editor_name).await?;
+    let rcj = library.get_rcj(&param.code);
+    if rcj.is_none() {
+        return Err(AppError(anyhow::anyhow!("补充人材机不存在".to_string())));
+    }
+    let id = IdGenerator::get_id();
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(InsertBcRcjCommand {
+                data: param.clone(),
+                rcj: rcj.unwrap().clone(),
+                id: id.clone(),
+            }),
+            "插入补充人材机 {{code}}".to_string(),
+            meta,
+        )
+        .await?;
+    res!(id)
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //新建补充定额
+        .route("/create_bc_de", post(create_bc_de))
+        //新建补充人材机
+        .route("/create_bc_rcj", post(create_bc_rcj))
+        //获取补充库
+        .route("/get_bc_library/{editor_name}", get(get_bc_library))
+        //工程项目补充定义导入本地库
+        .route(
+            "/import_bc_to_local/{editor_name}",
+            post(import_bc_to_local),
+        )
+        //插入补充定额
+        .route("/insert_bc_de", post(insert_bc_de))
+        //插入补充人材机
+        .route("/insert_bc_rcj", post(insert_bc_rcj))
+}
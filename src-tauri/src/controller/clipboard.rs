@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use axum::{
+    routing::{get, post},
+    Json, Router,
+};
+use mf_model::types::NodeId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::clipboard::{PasteCommand, RemoveNodesCommand},
+    error::AppError,
+    res,
+    response::Res,
+    utils::{
+        clipboard::{clear_clipboard, copy_nodes, get_clipboard, set_clipboard, Clipboard},
+        price::{check_children_unlocked, check_unlocked},
+    },
+    ContextHelper, ResponseResult,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CopyPost {
+    pub editor_name: String,
+    pub ids: Vec<NodeId>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PastePost {
+    /// 目标工程项目 可与复制来源不同
+    pub editor_name: String,
+    /// 目标节点
+    pub parent_id: NodeId,
+}
+
+/// 剪贴板内容摘要
+#[derive(Debug, Serialize)]
+pub struct ClipboardInfo {
+    pub editor_name: String,
+    pub cut: bool,
+    pub ids: Vec<NodeId>,
+    /// 顶层节点类型
+    pub types: Vec<String>,
+    /// 节点总数
+    pub node_count: usize,
+}
+
+async fn copy_to_clipboard(param: &CopyPost, cut: bool) -> ResponseResult<ClipboardInfo> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let (ids, nodes) = copy_nodes(&doc, &param.ids)?;
+    let info = ClipboardInfo {
+        editor_name: param.editor_name.clone(),
+        cut,
+        ids: ids.clone(),
+        types: nodes.iter().map(|node| node.r#type.clone()).collect(),
+        node_count: nodes.iter().map(|node| node.count()).sum(),
+    };
+    set_clipboard(Clipboard {
+        editor_name: param.editor_name.clone(),
+        ids,
+        cut,
+        nodes,
+    });
+    res!(info)
+}
+
+/// 复制
+pub async fn copy(Json(param): Json<CopyPost>) -> ResponseResult<ClipboardInfo> {
+    copy_to_clipboard(&param, false).await
+}
+
+/// 剪切 粘贴时删除来源节点
+pub async fn cut(Json(param): Json<CopyPost>) -> ResponseResult<ClipboardInfo> {
+    copy_to_clipboard(&param, true).await
+}
+
+/// 获取剪贴板内容
+pub async fn get_info() -> ResponseResult<Option<ClipboardInfo>> {
+    res!(get_clipboard().map(|clipboard| ClipboardInfo {
+        types: clipboard
+            .nodes
+            .iter()
+            .map(|node| node.r#type.clone())
+            .collect(),
+        node_count: clipboard.nodes.iter().map(|node| node.count()).sum(),
+        editor_name: clipboard.editor_name,
+        cut: clipboard.cut,
+        ids: clipboard.ids,
+    }))
+}
+
+/// 跨工程项目剪切 粘贴前检查来源节点可以删除 避免粘贴后来源节点删除失败
+async fn check_cut_source(clipboard: &Clipboard) -> Result<(), AppError> {
+    let source = ContextHelper::get_editor(&clipboard.editor_name);
+    if source.is_none() {
+        return Err(AppError(anyhow::anyhow!(
+            "剪切来源工程项目已关闭".to_string()
+        )));
+    }
+    let doc = source.unwrap().doc().await;
+    for id in clipboard.ids.iter() {
+        if doc.get_node(id).is_none() {
+            return Err(AppError(anyhow::anyhow!("剪切的节点 {} 已不存在", id)));
+        }
+        check_unlocked(&doc, id).map_err(|e| AppError(anyhow::anyhow!(e)))?;
+        // 与锁定插件一致 删除时检查父节点
+        if let Some(parent) = doc.get_parent_node(id) {
+            check_children_unlocked(&doc, &parent.id).map_err(|e| AppError(anyhow::anyhow!(e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// 粘贴 返回新节点 id
+pub async fn paste(Json(param): Json<PastePost>) -> ResponseResult<Vec<NodeId>> {
+    let clipboard = match get_clipboard() {
+        Some(clipboard) => clipboard,
+        None => return Err(AppError(anyhow::anyhow!("剪贴板为空".to_string()))),
+    };
+    let mut nodes = clipboard.nodes.clone();
+    for node in nodes.iter_mut() {
+        node.regenerate_ids();
+    }
+    let new_ids: Vec<NodeId> = nodes.iter().map(|node| node.id.clone()).collect();
+    let same_editor = clipboard.editor_name == param.editor_name;
+    if clipboard.cut && !same_editor {
+        check_cut_source(&clipboard).await?;
+    }
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(PasteCommand {
+                editor_name: param.editor_name.clone(),
+                parent_id: param.parent_id.clone(),
+                nodes,
+                remove_ids: if clipboard.cut && same_editor {
+                    clipboard.ids.clone()
+                } else {
+                    vec![]
+                },
+            }),
+            if clipboard.cut {
+                "剪切粘贴".to_string()
+            } else {
+                "粘贴".to_string()
+            },
+            meta,
+        )
+        .await?;
+    drop(editor);
+    if clipboard.cut {
+        // 跨工程项目剪切 粘贴成功后再删除来源节点 删除失败时保留剪贴板
+        if !same_editor {
+            let source = ContextHelper::get_editor(&clipboard.editor_name);
+            if source.is_none() {
+                return Err(AppError(anyhow::anyhow!(
+                    "已粘贴 剪切来源工程项目已关闭 来源节点未删除".to_string()
+                )));
+            }
+            let mut source = source.unwrap();
+            let meta = serde_json::to_value(&clipboard.ids)?;
+            source
+                .command_with_meta(
+                    Arc::new(RemoveNodesCommand {
+                        editor_name: clipboard.editor_name.clone(),
+                        ids: clipboard.ids.clone(),
+                    }),
+                    "剪切".to_string(),
+                    meta,
+                )
+                .await
+                .map_err(|e| AppError(anyhow::anyhow!("已粘贴 删除来源节点失败: {}", e)))?;
+        }
+        clear_clipboard();
+    }
+    res!(new_ids)
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //复制
+        .route("/copy", post(copy))
+        //剪切
+        .route("/cut", post(cut))
+        //获取剪贴板内容
+        .route("/get", get(get_info))
+        //粘贴
+        .route("/paste", post(paste))
+}
@@ -0,0 +1,91 @@
+use std::{path::Path, sync::Arc};
+
+use axum::{routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::drqd::{DrqdCommand, DrqdRequest},
+    error::AppError,
+    import::{
+        qd::{build_plan, QdColumnMapping, QdImportPlan},
+        table::{read_table, TableData},
+    },
+    res,
+    response::Res,
+    ContextHelper, ResponseResult,
+};
+
+/// 预览时返回的最大行数
+const PREVIEW_ROWS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct DrqdPreviewPost {
+    pub file_path: String,
+    pub sheet: Option<String>,
+}
+
+/// 文件预览 供用户选择工作表及列映射
+#[derive(Debug, Serialize)]
+pub struct DrqdPreview {
+    pub table: TableData,
+    /// 根据表头猜测的列映射
+    pub mapping: Option<QdColumnMapping>,
+}
+
+/// 预览导入文件
+pub async fn preview(Json(param): Json<DrqdPreviewPost>) -> ResponseResult<DrqdPreview> {
+    let mut table = read_table(Path::new(&param.file_path), param.sheet.as_deref())?;
+    let mapping = QdColumnMapping::guess(&table.rows);
+    table.rows.truncate(PREVIEW_ROWS);
+    res!(DrqdPreview { table, mapping })
+}
+
+/// 导入清单 dry_run 时只返回解析结果
+pub async fn import_qd(Json(param): Json<DrqdRequest>) -> ResponseResult<QdImportPlan> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let table = read_table(Path::new(&param.file_path), param.sheet.as_deref())?;
+    let mapping = match param
+        .mapping
+        .clone()
+        .or_else(|| QdColumnMapping::guess(&table.rows))
+    {
+        Some(mapping) => mapping,
+        None => {
+            return Err(AppError(anyhow::anyhow!(
+                "未找到表头 请指定列映射".to_string()
+            )))
+        }
+    };
+    let plan = build_plan(&table.rows, &mapping);
+    if param.dry_run {
+        return res!(plan);
+    }
+    if plan.nodes.is_empty() {
+        return Err(AppError(anyhow::anyhow!("没有可导入的清单".to_string())));
+    }
+    let meta = serde_json::to_value(&param)?;
+    editor
+        .command_with_meta(
+            Arc::new(DrqdCommand {
+                editor_name: param.editor_name.clone(),
+                parent_id: param.parent_id.clone(),
+                nodes: plan.nodes.clone(),
+            }),
+            "导入清单 {{file_path}}".to_string(),
+            meta,
+        )
+        .await?;
+    res!(plan)
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //预览导入文件
+        .route("/preview", post(preview))
+        //导入清单
+        .route("/import_qd", post(import_qd))
+}
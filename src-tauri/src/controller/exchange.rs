@@ -0,0 +1,251 @@
+use std::{path::Path, sync::Arc};
+
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use mf_state::transaction::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::exchange::ImportTreeCommand,
+    controller::gcxm::{create_editor, GcxmPost},
+    error::AppError,
+    exchange::{
+        json_tree::{check_tree, export_tree, parse_tree, JsonTreeCheck},
+        ExchangeIssue,
+    },
+    nodes::{
+        column::{check_columns, parse_columns},
+        gcxm::{CUSTOM_COLUMNS_ATTR, GCXM_STR},
+    },
+    res,
+    response::Res,
+    ContextHelper, ResponseResult,
+};
+
+/// 执行导入事务 errors 为导入前校验发现的错误
+/// 新建的工程项目校验未通过或导入失败时移除编辑器
+async fn run_import(
+    editor_name: &str,
+    command: Arc<dyn Command>,
+    meta: serde_json::Value,
+    created: bool,
+    errors: &[ExchangeIssue],
+) -> Result<(), AppError> {
+    let result = if !errors.is_empty() {
+        Err(check_error(errors))
+    } else {
+        match ContextHelper::get_editor(editor_name) {
+            Some(mut editor) => editor
+                .command_with_meta(command, "导入 {{file_path}}".to_string(), meta)
+                .await
+                .map_err(|e| AppError(e.into())),
+            None => Err(AppError(anyhow::anyhow!("工程项目不存在".to_string()))),
+        }
+    };
+    if result.is_err() && created {
+        ContextHelper::remove_editor(editor_name);
+    }
+    result
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsonTreeExportPost {
+    pub editor_name: String,
+    /// 导出子树的根节点 id
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JsonTreeImportPost {
+    /// 本地文件路径
+    pub file_path: String,
+    /// 导入到已有工程项目 为空时创建新工程项目
+    pub editor_name: Option<String>,
+    /// 导入到已有工程项目时的目标节点
+    pub parent_id: Option<String>,
+    /// 重新生成 id 不重新生成时 id 不能与已有节点冲突
+    #[serde(default = "default_regenerate_ids")]
+    pub regenerate_ids: bool,
+}
+
+fn default_regenerate_ids() -> bool {
+    true
+}
+
+/// JSON 树导入结果
+#[derive(Debug, Serialize)]
+pub struct JsonTreeImportReport {
+    pub editor_name: String,
+    pub node_count: usize,
+    /// 已忽略的未知属性
+    pub warnings: Vec<ExchangeIssue>,
+}
+
+/// 校验未通过时的错误信息 最多列出 5 项
+fn check_error(errors: &[ExchangeIssue]) -> AppError {
+    let details: Vec<String> = errors
+        .iter()
+        .take(5)
+        .map(|issue| format!("{}: {}", issue.path, issue.message))
+        .collect();
+    AppError(anyhow::anyhow!(
+        "导入校验未通过 共 {} 项 {}",
+        errors.len(),
+        details.join("; ")
+    ))
+}
+
+/// 导出 JSON 树 以附件形式返回
+pub async fn export_json(Json(param): Json<JsonTreeExportPost>) -> Result<Response, AppError> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let document = export_tree(&doc, &param.id)?;
+    let name = document
+        .root
+        .attrs
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or(&document.root.r#type)
+        .to_string();
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename*=UTF-8''{}",
+                    super::export::url_encode(&format!("{}.json", name))
+                ),
+            ),
+        ],
+        Body::from(serde_json::to_vec_pretty(&document)?),
+    )
+        .into_response())
+}
+
+/// 导入 JSON 树 新建工程项目或导入到已有节点下
+pub async fn import_json(
+    Json(param): Json<JsonTreeImportPost>,
+) -> ResponseResult<JsonTreeImportReport> {
+    let content = std::fs::read(Path::new(&param.file_path))?;
+    let mut root = parse_tree(&content)?.root;
+    if param.regenerate_ids {
+        root.regenerate_ids();
+    }
+    let mut check = JsonTreeCheck::default();
+    let meta = serde_json::to_value(param.clone())?;
+
+    let (editor_name, command, created) = match (&param.editor_name, &param.parent_id) {
+        (Some(editor_name), Some(parent_id)) => {
+            let editor = ContextHelper::get_editor(editor_name);
+            if editor.is_none() {
+                return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+            }
+            let editor = editor.unwrap();
+            let doc = editor.doc().await;
+            let schema = editor.get_state().await.schema();
+            let parent_type = match doc.get_node(parent_id) {
+                Some(parent) => parent.r#type.to_string(),
+                None => return Err(AppError(anyhow::anyhow!("目标节点不存在".to_string()))),
+            };
+            // 工程项目文件导入到已有节点下时 导入其下属节点
+            let mut nodes = if root.r#type == GCXM_STR {
+                root.children.clone()
+            } else {
+                vec![root.clone()]
+            };
+            for node in nodes.iter_mut() {
+                check_tree(&schema, Some(&parent_type), node, "", &mut check);
+                if !param.regenerate_ids {
+                    node.check_ids(&doc, &mut check.errors);
+                }
+            }
+            (
+                editor_name.clone(),
+                ImportTreeCommand {
+                    editor_name: editor_name.clone(),
+                    parent_id: parent_id.clone(),
+                    parent_attrs: None,
+                    nodes,
+                    replace: false,
+                },
+                false,
+            )
+        }
+        _ => {
+            if root.r#type != GCXM_STR {
+                return Err(AppError(anyhow::anyhow!(
+                    "新建工程项目需要导入完整的工程项目数据".to_string()
+                )));
+            }
+            let id = root.id.clone();
+            if ContextHelper::get_editor(&id).is_some() {
+                return Err(AppError(anyhow::anyhow!(
+                    "工程项目已打开 请选择重新生成 id".to_string()
+                )));
+            }
+            let name = root
+                .attrs
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("工程项目")
+                .to_string();
+            let custom_columns = check_columns(&parse_columns(root.attrs.get(CUSTOM_COLUMNS_ATTR)))
+                .map_err(|e| AppError(anyhow::anyhow!(e)))?;
+            create_editor(Arc::new(GcxmPost {
+                name,
+                id: Some(id.clone()),
+                custom_columns,
+            }))
+            .await?;
+            let schema = ContextHelper::get_editor(&id)
+                .unwrap()
+                .get_state()
+                .await
+                .schema();
+            check_tree(&schema, None, &mut root, "", &mut check);
+            (
+                id.clone(),
+                ImportTreeCommand {
+                    editor_name: id.clone(),
+                    parent_id: id,
+                    parent_attrs: Some(root.attrs_map()),
+                    nodes: root.children.clone(),
+                    replace: true,
+                },
+                true,
+            )
+        }
+    };
+    let node_count = command.nodes.iter().map(|node| node.count()).sum();
+    run_import(
+        &editor_name,
+        Arc::new(command),
+        meta,
+        created,
+        &check.errors,
+    )
+    .await?;
+    res!(JsonTreeImportReport {
+        editor_name,
+        node_count,
+        warnings: check.warnings,
+    })
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //导出 JSON 树
+        .route("/export_json", post(export_json))
+        //导入 JSON 树
+        .route("/import_json", post(import_json))
+}
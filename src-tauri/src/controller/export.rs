@@ -0,0 +1,62 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    export::xlsx::{export_xlsx, XLSX_CONTENT_TYPE},
+    ContextHelper,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportPost {
+    pub editor_name: String,
+    /// 单位工程 或 工程项目 id
+    pub id: String,
+}
+
+/// 导出 Excel 以附件形式返回文件
+pub async fn export_excel(Json(param): Json<ExportPost>) -> Result<Response, AppError> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let (file_name, content) = export_xlsx(&doc, &param.id)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, XLSX_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename*=UTF-8''{}", url_encode(&file_name)),
+            ),
+        ],
+        Body::from(content),
+    )
+        .into_response())
+}
+
+/// 文件名 百分号编码 用于 Content-Disposition
+pub fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //导出 Excel
+        .route("/excel", post(export_excel))
+}
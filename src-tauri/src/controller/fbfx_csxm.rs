@@ -1,15 +1,17 @@
 use std::sync::Arc;
 
-use mf_model::{id_generator::IdGenerator, node::Node};
+use mf_model::{id_generator::IdGenerator, types::NodeId};
 
 use crate::{
     commands::{
-        fbfx_csxm::{DeleteFbfxCsxmCommand, InsertFbfxCsxmCommand},
-        AddRequest, DeleteNodeRequest,
+        fbfx_csxm::{
+            BatchFbfxCsxmCommand, BatchOperation, BatchRequest, DeleteFbfxCsxmCommand,
+            InsertFbfxCsxmCommand, LockPriceCommand, LockPriceRequest, UpdateFbfxCsxmCommand,
+        },
+        AddRequest, DeleteNodeRequest, UpdateAttrsRequest,
     },
     controller::GcxmTreeItem,
     error::AppError,
-    nodes::fbfx_csxm::{DE_RCJ_STR, DE_STR, FB_STR, QD_STR},
     res,
     response::Res,
     ContextHelper, ResponseResult,
@@ -54,6 +56,86 @@ pub async fn delete_fbfx_csxm(Json(param): Json<DeleteNodeRequest>) -> ResponseR
     res!("success".to_string())
 }
 
+/// 更新分部分项 措施项目 节点属性
+pub async fn update_fbfx_csxm(Json(param): Json<UpdateAttrsRequest>) -> ResponseResult<String> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(UpdateFbfxCsxmCommand {
+                data: param.clone(),
+            }),
+            "修改 分部分项 节点".to_string(),
+            meta,
+        )
+        .await?;
+    res!("success".to_string())
+}
+
+/// 批量 新增、修改、删除 分部分项 措施项目 行 返回新增节点 id(按新增操作顺序)
+pub async fn batch_fbfx_csxm(Json(mut param): Json<BatchRequest>) -> ResponseResult<Vec<NodeId>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    if param.operations.is_empty() {
+        return Err(AppError(anyhow::anyhow!("没有需要执行的操作".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let mut ids = Vec::new();
+    for operation in param.operations.iter_mut() {
+        match operation {
+            BatchOperation::Add(data) => {
+                data.editor_name = param.editor_name.clone();
+                let id = data.id.get_or_insert_with(IdGenerator::get_id).clone();
+                ids.push(id);
+            }
+            BatchOperation::Update(data) => data.editor_name = param.editor_name.clone(),
+            BatchOperation::Delete(data) => data.editor_name = param.editor_name.clone(),
+        }
+    }
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(BatchFbfxCsxmCommand {
+                data: param.clone(),
+            }),
+            format!("批量修改 分部分项 {} 项", param.operations.len()),
+            meta,
+        )
+        .await?;
+    res!(ids)
+}
+
+/// 锁定/解锁 清单、定额 综合单价
+pub async fn lock_price(Json(param): Json<LockPriceRequest>) -> ResponseResult<String> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    let description = if param.lock {
+        "锁定综合单价"
+    } else {
+        "解锁综合单价"
+    };
+    editor
+        .command_with_meta(
+            Arc::new(LockPriceCommand {
+                data: param.clone(),
+            }),
+            description.to_string(),
+            meta,
+        )
+        .await?;
+    res!("success".to_string())
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FbfxCsxmPost {
     pub editor_name: String,
@@ -66,30 +148,13 @@ pub async fn get_fbfx_csxm_tree(Json(param): Json<FbfxCsxmPost>) -> ResponseResu
     if editor.is_none() {
         return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
     }
-    let  editor = editor.unwrap();
+    let editor = editor.unwrap();
     let doc = editor.doc().await;
-    let node: Option<Arc<Node>> = doc.get_node(&param.id);
-    if node.is_none() {
-        return Err(AppError(anyhow::anyhow!(
-            "分部分项 措施项目 跟节点不存在".to_string()
-        )));
-    }
-    let node = node.unwrap();
-    let mut nodes: Vec<Arc<Node>> = doc
-        .descendants(&param.id)
-        .iter()
-        .filter(|n| {
-            n.r#type == FB_STR || n.r#type == QD_STR || n.r#type == DE_STR || n.r#type == DE_RCJ_STR
-        })
-        .cloned()
-        .collect();
-    nodes.push(node);
-    let parent_map = &doc.get_inner().parent_map;
-    if let Some(root_item) = GcxmTreeItem::from_nodes(param.id, nodes, parent_map) {
+    if let Some(root_item) = GcxmTreeItem::fbfx_csxm_tree(&doc, &param.id) {
         res!(root_item)
     } else {
         Err(AppError(anyhow::anyhow!(
-            "无法构建工程树,未找到分部分项 措施项目 跟节点"
+            "分部分项 措施项目 跟节点不存在".to_string()
         )))
     }
 }
@@ -100,6 +165,12 @@ pub fn build_app() -> Router {
         .route("/", post(add_fbfx_csxm))
         //删除分部分项 措施项目 节点
         .route("/delete_fbfx_csxm", post(delete_fbfx_csxm))
+        //更新分部分项 措施项目 节点属性
+        .route("/update_fbfx_csxm", post(update_fbfx_csxm))
+        //批量 新增、修改、删除
+        .route("/batch", post(batch_fbfx_csxm))
+        //锁定/解锁综合单价
+        .route("/lock_price", post(lock_price))
         //获取分部分项 措施项目树
         .route("/get_fbfx_csxm_tree", post(get_fbfx_csxm_tree))
 }
@@ -0,0 +1,63 @@
+use axum::{routing::post, Json, Router};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    res,
+    response::Res,
+    utils::fyhz::{Fyhz, XmhzItem},
+    ContextHelper, ResponseResult,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct FyhzPost {
+    pub editor_name: String,
+    /// 单位工程 id
+    pub id: String,
+}
+
+/// 获取单位工程费用汇总
+pub async fn get_fyhz(Json(param): Json<FyhzPost>) -> ResponseResult<Fyhz> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    match Fyhz::build(&doc, &param.id) {
+        Some(fyhz) => res!(fyhz),
+        None => Err(AppError(anyhow::anyhow!("单位工程不存在".to_string()))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct XmhzPost {
+    pub editor_name: String,
+    /// 工程项目、单项工程 或 单位工程 id 为空时为整个工程项目
+    pub id: Option<String>,
+}
+
+/// 获取工程项目汇总表
+pub async fn get_xmhz(Json(param): Json<XmhzPost>) -> ResponseResult<XmhzItem> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let id = param.id.clone().unwrap_or(param.editor_name.clone());
+    match XmhzItem::build(&doc, &id) {
+        Some(xmhz) => res!(xmhz),
+        None => Err(AppError(anyhow::anyhow!(
+            "只能汇总工程项目、单项工程或单位工程".to_string()
+        ))),
+    }
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //获取费用汇总
+        .route("/get_fyhz", post(get_fyhz))
+        //获取工程项目汇总表
+        .route("/get_xmhz", post(get_xmhz))
+}
@@ -14,21 +14,30 @@ use serde_json::Value;
 
 use crate::{
     commands::{
-        gcxm::{AddFootNoteCammand, DeleteGcxmCammand, InsertChildCammand},
-        AddRequest, DeleteNodeRequest,
-    }, controller::{get_data_tree, get_history, get_inc_data, GcxmTreeItem}, error::AppError, initialize::editor::{init_collab_editor, init_collab_options, init_editor, init_options}, nodes::gcxm::{DWGC_STR, DXGC_STR, GCXM_STR}, res, response::Res, ContextHelper, ResponseResult
+        gcxm::{AddFootNoteCammand, DeleteGcxmCammand, InsertChildCammand, MoveNodeCommand, UpdateGcxmAttrsCammand},
+        AddRequest, DeleteNodeRequest, MoveRequest, UpdateAttrsRequest,
+    }, controller::{get_data_tree, get_history, get_inc_data, get_inc_snapshot, subscribe_inc, GcxmTreeItem}, error::AppError, initialize::editor::{init_collab_editor, init_collab_options, init_editor, init_options}, nodes::{column::{check_columns, CustomColumn}, gcxm::{CUSTOM_COLUMNS_ATTR, DWGC_STR, DXGC_STR, GCXM_STR}}, res, response::Res, ContextHelper, ResponseResult
 };
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct GcxmPost {
     pub name: String,
     pub id: Option<String>,
+    /// 分部、清单、定额 自定义列 创建编辑器时合并到 schema
+    #[serde(default)]
+    pub custom_columns: Vec<CustomColumn>,
 }
 
 impl GcxmPost {
     pub fn to_attr_map(&self) -> HashMap<String, Value> {
         let mut attr = HashMap::new();
         Self::insert_str(&mut attr, "name", &Some(self.name.clone()));
+        if !self.custom_columns.is_empty() {
+            attr.insert(
+                CUSTOM_COLUMNS_ATTR.to_string(),
+                serde_json::to_value(&self.custom_columns).unwrap_or_default(),
+            );
+        }
         attr
     }
     fn insert_str(map: &mut HashMap<String, Value>, key: &str, value: &Option<String>) {
@@ -55,20 +64,22 @@ impl NodePoolFnTrait for GcxmPost {
 }
 
 pub async fn create_editor(create_callback: Arc<GcxmPost>) -> anyhow::Result<()> {
-    let option = init_options(create_callback.clone()).await;
+    let option = init_options(create_callback.clone(), &create_callback.custom_columns).await;
     let editor = init_editor(option).await;
     ContextHelper::set_editor(&create_callback.id.clone().unwrap(), Box::new(editor));
     Ok(())
 }
 
 pub async fn create_collab_editor(create_callback: Arc<GcxmPost>) -> anyhow::Result<()> {
-    let option = init_collab_options(create_callback.clone(),create_callback.id.clone().unwrap()).await;
+    let option = init_collab_options(create_callback.clone(),create_callback.id.clone().unwrap(),&create_callback.custom_columns).await;
     let editor = init_collab_editor(option).await;
     ContextHelper::set_editor(&create_callback.id.clone().unwrap(), Box::new(editor));
     Ok(())
 }
 ///创建工程项目
 pub async fn new_project(Json(mut param): Json<GcxmPost>) -> ResponseResult<GcxmTreeItem> {
+    param.custom_columns =
+        check_columns(&param.custom_columns).map_err(|e| AppError(anyhow::anyhow!(e)))?;
     let id: String = IdGenerator::get_id();
     param.id = Some(id.clone());
     create_collab_editor(Arc::new(param.clone())).await?;
@@ -110,12 +121,11 @@ pub async fn get_gcxm_tree(Path(editor_name): Path<String>) -> ResponseResult<Gc
     }
     let editor = editor.unwrap();
     let doc = editor.doc().await;
-    let nodes: Vec<Arc<Node>> = doc.parallel_query(Box::new(|node: &Node| {
+    // 按文档顺序构建 移动节点后顺序与文档一致
+    let filter = |node: &Node| {
         node.r#type == DWGC_STR || node.r#type == DXGC_STR || node.r#type == GCXM_STR
-    }));
-
-    let parent_map = &doc.get_inner().parent_map;
-    if let Some(root_item) = GcxmTreeItem::from_nodes(editor_name, nodes, parent_map) {
+    };
+    if let Some(root_item) = GcxmTreeItem::from_doc(&doc, &editor_name, &filter) {
         res!(root_item)
     } else {
         Err(AppError(anyhow::anyhow!("无法构建工程树,未找到根节点")))
@@ -166,6 +176,46 @@ pub async fn add_footnote(Json(param): Json<AddFootNoteCammand>) -> ResponseResu
     res!(())
 }
 
+///更新工程项目节点属性
+pub async fn update_gcxm_attrs(Json(param): Json<UpdateAttrsRequest>) -> ResponseResult<()> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(UpdateGcxmAttrsCammand {
+                data: param.clone(),
+            }),
+            "修改id：{{id}}属性".to_string(),
+            meta,
+        )
+        .await?;
+    res!(())
+}
+
+///移动节点
+pub async fn move_node(Json(param): Json<MoveRequest>) -> ResponseResult<()> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(MoveNodeCommand {
+                data: param.clone(),
+            }),
+            "移动id：{{id}}".to_string(),
+            meta,
+        )
+        .await?;
+    res!(())
+}
+
 pub fn build_app() -> Router {
     Router::new()
         //创建新工程项目
@@ -178,10 +228,18 @@ pub fn build_app() -> Router {
         .route("/add_footnote", post(add_footnote))
         //删除工程项目
         .route("/delete_gcxm", post(delete_gcxm))
+        //更新节点属性
+        .route("/update_attrs", post(update_gcxm_attrs))
+        //移动节点 上移、下移、移动到指定位置或其他父节点下
+        .route("/move_node", post(move_node))
         // 历史记录
         .route("/get_history", post(get_history))
         //获取数据树
         .route("/get_data_tree", post(get_data_tree))
         //获取增量数据
         .route("/get_inc_data/{editor_name}", get(get_inc_data))
+        //获取镜像快照 及其对应的增量数据版本
+        .route("/get_inc_snapshot/{editor_name}", get(get_inc_snapshot))
+        //订阅增量数据 SSE 推送
+        .route("/subscribe_inc/{editor_name}", get(subscribe_inc))
 }
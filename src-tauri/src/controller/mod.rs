@@ -1,43 +1,129 @@
-use std::sync::Arc;
+use std::{convert::Infallible, sync::Arc};
 
-use axum::{extract::Path, Json};
+use axum::{
+    extract::{Path, Query},
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
 use chrono::{DateTime, Local};
 use mf_core::types::HistoryEntryWithMeta;
-use mf_model::{attrs::Attrs, mark::Mark, node::Node, types::NodeId};
+use mf_model::{attrs::Attrs, mark::Mark, node::Node, node_pool::NodePool, types::NodeId};
 use mf_template::render;
 use serde::{Deserialize, Serialize};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
 
 use crate::{
     error::AppError,
-    plugins::inc::{Operation, Operations},
+    nodes::fbfx_csxm::{DE_RCJ_STR, DE_STR, FB_STR, QD_STR},
+    plugins::inc::{IncFeed, IncState, INC_PLUGIN_KEY},
     res,
     response::Res,
+    utils::{
+        mirror::Mirror,
+        node::children,
+        push::{subscribe, IncSubscription},
+    },
     ContextHelper, ResponseResult,
 };
 
+pub mod bc;
+pub mod clipboard;
 pub mod djgc;
+pub mod drqd;
+pub mod exchange;
+pub mod export;
 pub mod fbfx_csxm;
+pub mod fyhz;
 pub mod gcxm;
 pub mod rcj;
+pub mod report;
+pub mod schema;
+pub mod tj;
+pub mod xmbm;
+pub mod zhdjfx;
+pub mod zjfa;
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GetDataTreeRequest {
     pub editor_name: String,
     pub id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct IncQuery {
+    /// 客户端已同步的版本 返回之后的增量数据
+    #[serde(default)]
+    pub since: u64,
+}
+
+/// 获取增量数据 返回 since 版本之后的全部变更
+/// 不会清除变更日志 多个客户端可各自按版本读取
 pub async fn get_inc_data(
     Path(editor_name): Path<String>,
-) -> ResponseResult<Option<Arc<Operations>>> {
+    Query(query): Query<IncQuery>,
+) -> ResponseResult<IncFeed> {
     let editor = ContextHelper::get_editor(&editor_name);
     if editor.is_none() {
         return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
     }
     let editor = editor.unwrap();
-    let manager = editor.get_state().await.resource_manager();
-    let operations = manager
-        .resource_table
-        .take::<Operations>("inc_data".to_string());
-    res!(operations)
+    let state = editor.get_state().await;
+    let inc_state = state
+        .get_field(INC_PLUGIN_KEY)
+        .and_then(|value| value.downcast_arc::<IncState>().ok());
+    match inc_state {
+        Some(inc_state) => res!(inc_state.since(query.since)),
+        None => Err(AppError(anyhow::anyhow!("增量数据插件未启用".to_string()))),
+    }
+}
+
+/// 获取镜像快照 与当前增量数据版本一致 之后按版本获取增量数据应用到镜像
+pub async fn get_inc_snapshot(Path(editor_name): Path<String>) -> ResponseResult<Mirror> {
+    let editor = ContextHelper::get_editor(&editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let state = editor.get_state().await;
+    let version = state
+        .get_field(INC_PLUGIN_KEY)
+        .and_then(|value| value.downcast_arc::<IncState>().ok())
+        .map(|inc_state| inc_state.version)
+        .unwrap_or_default();
+    res!(Mirror::from_doc(&state.doc(), &editor_name, version))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeQuery {
+    /// 只订阅该节点子树内的变更 如分部分项表只订阅其根节点
+    pub root_id: Option<NodeId>,
+}
+
+/// 订阅增量数据 SSE 每个事务推送一条 inc 事件
+/// 订阅方处理过慢丢失事件时推送 resync 事件 客户端应按版本重新获取增量数据
+pub async fn subscribe_inc(
+    Path(editor_name): Path<String>,
+    Query(query): Query<SubscribeQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    if ContextHelper::get_editor(&editor_name).is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let subscription = IncSubscription {
+        editor_name,
+        root_id: query.root_id,
+    };
+    let stream = BroadcastStream::new(subscribe()).filter_map(move |event| match event {
+        Ok(event) => subscription
+            .filter(&event)
+            .and_then(|push| Event::default().event("inc").json_data(push).ok())
+            .map(Ok),
+        Err(BroadcastStreamRecvError::Lagged(_)) => {
+            Some(Ok(Event::default().event("resync").data("")))
+        }
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
 
 /// 获取数据树
@@ -137,6 +223,33 @@ pub struct GcxmTreeItem {
 }
 
 impl GcxmTreeItem {
+    /// 按文档顺序构建子树 只保留满足条件的子节点
+    pub fn from_doc<F>(doc: &NodePool, id: &NodeId, filter: &F) -> Option<Self>
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let node = doc.get_node(id)?;
+        let children = children(doc, id)
+            .iter()
+            .filter(|child| filter(child))
+            .filter_map(|child| Self::from_doc(doc, &child.id, filter))
+            .collect();
+        Some(GcxmTreeItem {
+            id: node.id.clone(),
+            r#type: node.r#type.to_string(),
+            attrs: node.attrs.clone(),
+            children,
+            marks: node.marks.iter().cloned().collect(),
+        })
+    }
+
+    /// 分部分项 措施项目树 包含 分部、清单、定额、定额人材机
+    pub fn fbfx_csxm_tree(doc: &NodePool, id: &NodeId) -> Option<Self> {
+        Self::from_doc(doc, id, &|n: &Node| {
+            n.r#type == FB_STR || n.r#type == QD_STR || n.r#type == DE_STR || n.r#type == DE_RCJ_STR
+        })
+    }
+
     fn from_nodes(
         root_id: NodeId,
         nodes: Vec<Arc<Node>>,
@@ -1 +1,174 @@
+use std::collections::HashMap;
 
+use axum::{routing::post, Json, Router};
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AppError,
+    nodes::{fbfx_csxm::DE_STR, rcj::RCJ_STR},
+    res,
+    response::Res,
+    utils::{
+        money::{get_decimal, Decimal},
+        node::{children_of_type, descendants_of_type, get_str},
+        price::{is_jgcl, is_zgcl, rcj_base_price, rcj_jc, rcj_price, PriceOptions, RcjKind},
+    },
+    ContextHelper, ResponseResult,
+};
+
+/// 人材机汇总行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RcjHzRow {
+    pub material_code: String,
+    pub material_name: String,
+    pub specification: String,
+    pub unit: String,
+    pub kind: RcjKind,
+    /// 暂估材料
+    pub zg: bool,
+    /// 甲供材料
+    pub jg: bool,
+    pub price_base: Decimal,
+    pub price_market: Decimal,
+    /// 总消耗量 = Σ 消耗量 × 定额工程量
+    pub quantity: Decimal,
+    /// 合价
+    pub total: Decimal,
+    /// 价差合计 = (市场价 - 定额基价) × 总消耗量
+    pub jc_total: Decimal,
+    /// 引用该人材机的节点
+    pub rcj_ids: Vec<NodeId>,
+}
+
+/// 汇总节点下的人材机 按 编码、名称、规格、单位、基价、市场价 合并
+pub fn collect_rcj_hz<F>(doc: &NodePool, id: &NodeId, filter: F) -> Vec<RcjHzRow>
+where
+    F: Fn(&Node) -> bool,
+{
+    let mut rows: Vec<RcjHzRow> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for de in descendants_of_type(doc, id, &[DE_STR]) {
+        let de_quantity = get_decimal(&de, "quantity");
+        for rcj in children_of_type(doc, &de.id, RCJ_STR) {
+            if !filter(&rcj) {
+                continue;
+            }
+            let price_base = rcj_base_price(&rcj);
+            let price_market = rcj_price(&rcj);
+            let quantity = get_decimal(&rcj, "resQty") * de_quantity;
+            let key = format!(
+                "{}|{}|{}|{}|{}|{}",
+                get_str(&rcj, "materialCode"),
+                get_str(&rcj, "materialName"),
+                get_str(&rcj, "specification"),
+                get_str(&rcj, "unit"),
+                price_base,
+                price_market
+            );
+            match index.get(&key) {
+                Some(i) => {
+                    let row = &mut rows[*i];
+                    row.quantity += quantity;
+                    row.total = row.quantity * row.price_market;
+                    row.jc_total = row.quantity * (row.price_market - row.price_base);
+                    row.rcj_ids.push(rcj.id.clone());
+                }
+                None => {
+                    index.insert(key, rows.len());
+                    rows.push(RcjHzRow {
+                        material_code: get_str(&rcj, "materialCode"),
+                        material_name: get_str(&rcj, "materialName"),
+                        specification: get_str(&rcj, "specification"),
+                        unit: get_str(&rcj, "unit"),
+                        kind: RcjKind::of(&rcj),
+                        zg: is_zgcl(&rcj),
+                        jg: is_jgcl(&rcj),
+                        price_base,
+                        price_market,
+                        quantity,
+                        total: quantity * price_market,
+                        jc_total: quantity * (price_market - price_base),
+                        rcj_ids: vec![rcj.id.clone()],
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RcjHzPost {
+    pub editor_name: String,
+    pub id: String,
+    /// all: 全部 zgcl: 暂估材料 jgcl: 甲供材料
+    pub filter: Option<String>,
+}
+
+/// 获取人材机汇总
+pub async fn get_rcj_hz(Json(param): Json<RcjHzPost>) -> ResponseResult<Vec<RcjHzRow>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    if doc.get_node(&param.id).is_none() {
+        return Err(AppError(anyhow::anyhow!("节点不存在".to_string())));
+    }
+    let rows = match param.filter.as_deref() {
+        Some("zgcl") => collect_rcj_hz(&doc, &param.id, is_zgcl),
+        Some("jgcl") => collect_rcj_hz(&doc, &param.id, is_jgcl),
+        _ => collect_rcj_hz(&doc, &param.id, |_| true),
+    };
+    res!(rows)
+}
+
+/// 获取暂估材料表
+pub async fn get_zgcl_list(Json(mut param): Json<RcjHzPost>) -> ResponseResult<Vec<RcjHzRow>> {
+    param.filter = Some("zgcl".to_string());
+    get_rcj_hz(Json(param)).await
+}
+
+/// 价差汇总表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JcTable {
+    pub id: NodeId,
+    /// 价差处理方式 price/separate
+    pub jc_mode: String,
+    pub rows: Vec<RcjHzRow>,
+    /// 价差合计
+    pub total: Decimal,
+}
+
+/// 获取价差汇总表 只列出市场价与定额基价不同的人材机
+pub async fn get_jc_list(Json(param): Json<RcjHzPost>) -> ResponseResult<JcTable> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    if doc.get_node(&param.id).is_none() {
+        return Err(AppError(anyhow::anyhow!("节点不存在".to_string())));
+    }
+    let rows = collect_rcj_hz(&doc, &param.id, |rcj| !rcj_jc(rcj).is_zero());
+    let total = rows.iter().map(|row| row.jc_total).sum();
+    res!(JcTable {
+        id: param.id.clone(),
+        jc_mode: PriceOptions::of(&doc, &param.id).jc_mode,
+        rows,
+        total,
+    })
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //获取人材机汇总
+        .route("/get_rcj_hz", post(get_rcj_hz))
+        //获取暂估材料表
+        .route("/get_zgcl_list", post(get_zgcl_list))
+        //获取价差汇总表
+        .route("/get_jc_list", post(get_jc_list))
+}
@@ -0,0 +1,99 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    report::{
+        pdf::html_to_pdf,
+        render_html,
+        template::{list_templates, template_dir, ReportTemplate},
+    },
+    res,
+    response::Res,
+    utils::node::get_str,
+    ContextHelper, ResponseResult,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReportPost {
+    pub editor_name: String,
+    /// 工程项目、单项工程 或 单位工程 id
+    pub id: String,
+    /// 模板名 按顺序输出 为空时输出全部模板
+    #[serde(default)]
+    pub templates: Vec<String>,
+}
+
+/// 获取报表模板列表
+pub async fn get_templates() -> ResponseResult<Vec<ReportTemplate>> {
+    res!(list_templates()?)
+}
+
+/// 获取报表模板目录 用户在此目录中新增、修改模板
+pub async fn get_template_dir() -> ResponseResult<String> {
+    res!(template_dir().display().to_string())
+}
+
+/// 渲染报表 返回 (文件名, HTML)
+async fn render(param: &ReportPost) -> Result<(String, String), AppError> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    let html = render_html(&doc, &param.id, &param.templates)?;
+    let name = doc
+        .get_node(&param.id)
+        .map(|node| get_str(&node, "name"))
+        .unwrap_or_default();
+    Ok((name, html))
+}
+
+/// 报表 HTML 预览
+pub async fn report_html(Json(param): Json<ReportPost>) -> Result<Response, AppError> {
+    let (_, html) = render(&param).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/html; charset=utf-8".to_string())],
+        Body::from(html),
+    )
+        .into_response())
+}
+
+/// 报表 PDF 以附件形式返回
+pub async fn report_pdf(Json(param): Json<ReportPost>) -> Result<Response, AppError> {
+    let (name, html) = render(&param).await?;
+    let content = html_to_pdf(&html).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename*=UTF-8''{}",
+                    super::export::url_encode(&format!("{}.pdf", name))
+                ),
+            ),
+        ],
+        Body::from(content),
+    )
+        .into_response())
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //获取报表模板列表
+        .route("/templates", get(get_templates))
+        //获取报表模板目录
+        .route("/template_dir", get(get_template_dir))
+        //报表 HTML
+        .route("/html", post(report_html))
+        //报表 PDF
+        .route("/pdf", post(report_pdf))
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use axum::{extract::Path, routing::get, Router};
+use mf_model::schema::{AttributeSpec, Schema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::AppError,
+    exchange::json_tree::content_allows,
+    nodes::{
+        column::{custom_columns, CustomColumn, CUSTOM_COLUMN_TYPES},
+        field::{field_spec, FieldSpec},
+    },
+    res,
+    response::Res,
+    ContextHelper, ResponseResult,
+};
+
+/// 属性定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaAttr {
+    pub name: String,
+    pub default: Option<Value>,
+    /// 有类型定义时为 decimal/integer/enum/string/bool
+    /// 否则按默认值推断 number/string/boolean/object/array 无默认值时为 any
+    pub r#type: String,
+    /// 类型定义 精度、枚举值、是否必填、取值范围
+    pub spec: Option<FieldSpec>,
+}
+
+/// 节点类型定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaNode {
+    pub name: String,
+    /// 说明 如 "清单"
+    pub desc: Option<String>,
+    /// 内容表达式
+    pub content: Option<String>,
+    pub group: Option<String>,
+    /// 允许的标记
+    pub marks: Option<String>,
+    /// 按内容表达式可直接包含的子节点类型
+    pub children: Vec<String>,
+    pub attrs: Vec<SchemaAttr>,
+}
+
+/// 标记定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaMark {
+    pub name: String,
+    pub desc: Option<String>,
+    pub attrs: Vec<SchemaAttr>,
+}
+
+/// 工程项目当前使用的 schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaInfo {
+    pub top_node: Option<String>,
+    pub nodes: Vec<SchemaNode>,
+    pub marks: Vec<SchemaMark>,
+    /// 分部、清单、定额 的自定义列 按定义顺序
+    pub custom_columns: Vec<CustomColumn>,
+}
+
+fn value_type(value: &Option<Value>) -> &'static str {
+    match value {
+        Some(Value::Number(_)) => "number",
+        Some(Value::String(_)) => "string",
+        Some(Value::Bool(_)) => "boolean",
+        Some(Value::Object(_)) => "object",
+        Some(Value::Array(_)) => "array",
+        Some(Value::Null) | None => "any",
+    }
+}
+
+/// 属性按名称排序 node_type 为空时为标记属性
+fn schema_attrs(
+    node_type: Option<&str>,
+    attrs: &Option<HashMap<String, AttributeSpec>>,
+    columns: &[CustomColumn],
+) -> Vec<SchemaAttr> {
+    let mut result: Vec<SchemaAttr> = attrs
+        .iter()
+        .flatten()
+        .map(|(name, spec)| {
+            let field = match node_type {
+                Some(node_type) => field_spec(node_type, name).cloned().or_else(|| {
+                    columns
+                        .iter()
+                        .find(|column| {
+                            CUSTOM_COLUMN_TYPES.contains(&node_type) && &column.key == name
+                        })
+                        .map(|column| column.spec())
+                }),
+                None => None,
+            };
+            SchemaAttr {
+                name: name.clone(),
+                r#type: match &field {
+                    Some(field) => field.r#type.name().to_string(),
+                    None => value_type(&spec.default).to_string(),
+                },
+                spec: field,
+                default: spec.default.clone(),
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+impl SchemaInfo {
+    /// columns 为工程项目的自定义列
+    pub fn from_schema(schema: &Schema, columns: Vec<CustomColumn>) -> Self {
+        let mut nodes: Vec<SchemaNode> = schema
+            .nodes
+            .iter()
+            .map(|(name, node_type)| {
+                let content = node_type.spec.content.clone();
+                let mut children: Vec<String> = match content.as_deref() {
+                    Some(content) if !content.is_empty() => schema
+                        .nodes
+                        .keys()
+                        .filter(|child| content_allows(schema, content, child))
+                        .cloned()
+                        .collect(),
+                    _ => vec![],
+                };
+                children.sort();
+                SchemaNode {
+                    name: name.clone(),
+                    desc: node_type.spec.desc.clone(),
+                    content,
+                    group: node_type.spec.group.clone(),
+                    marks: node_type.spec.marks.clone(),
+                    children,
+                    attrs: schema_attrs(Some(name), &node_type.spec.attrs, &columns),
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut marks: Vec<SchemaMark> = schema
+            .marks
+            .iter()
+            .map(|(name, mark_type)| SchemaMark {
+                name: name.clone(),
+                desc: mark_type.spec.desc.clone(),
+                attrs: schema_attrs(None, &mark_type.spec.attrs, &[]),
+            })
+            .collect();
+        marks.sort_by(|a, b| a.name.cmp(&b.name));
+        Self {
+            top_node: schema
+                .top_node_type
+                .as_ref()
+                .map(|node_type| node_type.name.clone()),
+            nodes,
+            marks,
+            custom_columns: columns,
+        }
+    }
+}
+
+/// 获取工程项目当前使用的 schema 前端据此生成表单和表格列
+pub async fn get_schema(Path(editor_name): Path<String>) -> ResponseResult<SchemaInfo> {
+    let editor = ContextHelper::get_editor(&editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let state = editor.unwrap().get_state().await;
+    let schema = state.schema();
+    res!(SchemaInfo::from_schema(
+        &schema,
+        custom_columns(&state.doc())
+    ))
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //获取 schema
+        .route("/{editor_name}", get(get_schema))
+}
@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{routing::post, Json, Router};
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::tj::{TjCommand, TjRequest},
+    error::AppError,
+    nodes::fbfx_csxm::QD_STR,
+    res,
+    response::Res,
+    utils::{
+        fyhz::gczj_total,
+        money::Decimal,
+        node::{descendants_of_type, get_str},
+        price::{is_locked, qd_amount, PriceOptions, QdAmount},
+    },
+    ContextHelper, ResponseResult,
+};
+
+/// 调价前后 清单对比行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TjQdRow {
+    pub id: NodeId,
+    pub project_code: String,
+    pub project_name: String,
+    pub quantity: Decimal,
+    pub locked: bool,
+    pub price_before: Decimal,
+    pub price_after: Decimal,
+    pub total_before: Decimal,
+    pub total_after: Decimal,
+}
+
+/// 调价报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TjReport {
+    pub id: NodeId,
+    pub target: Option<Decimal>,
+    /// 调价前 工程造价
+    pub total_before: Decimal,
+    /// 调价后 工程造价
+    pub total_after: Decimal,
+    pub rows: Vec<TjQdRow>,
+}
+
+/// 节点下各清单的 综合单价、合价
+fn qd_prices(doc: &NodePool, id: &NodeId) -> HashMap<NodeId, QdAmount> {
+    descendants_of_type(doc, id, &[QD_STR])
+        .iter()
+        .map(|qd| {
+            let options = PriceOptions::of(doc, &qd.id);
+            (qd.id.clone(), qd_amount(doc, qd, &options))
+        })
+        .collect()
+}
+
+/// 调价 返回调价前后各清单对比
+pub async fn tj(Json(param): Json<TjRequest>) -> ResponseResult<TjReport> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let before = editor.doc().await;
+    if before.get_node(&param.id).is_none() {
+        return Err(AppError(anyhow::anyhow!("节点不存在".to_string())));
+    }
+    let prices_before = qd_prices(&before, &param.id);
+    let total_before = gczj_total(&before, &param.id);
+
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(TjCommand {
+                data: param.clone(),
+            }),
+            "调价".to_string(),
+            meta,
+        )
+        .await?;
+
+    let after = editor.doc().await;
+    let prices_after = qd_prices(&after, &param.id);
+    let rows = descendants_of_type(&after, &param.id, &[QD_STR])
+        .iter()
+        .map(|qd| {
+            let before = prices_before.get(&qd.id).cloned().unwrap_or_default();
+            let after = prices_after.get(&qd.id).cloned().unwrap_or_default();
+            TjQdRow {
+                id: qd.id.clone(),
+                project_code: get_str(qd, "projectCode"),
+                project_name: get_str(qd, "projectName"),
+                quantity: after.quantity,
+                locked: is_locked(qd),
+                price_before: before.price,
+                price_after: after.price,
+                total_before: before.total,
+                total_after: after.total,
+            }
+        })
+        .collect();
+    res!(TjReport {
+        id: param.id.clone(),
+        target: param.target,
+        total_before,
+        total_after: gczj_total(&after, &param.id),
+        rows,
+    })
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //调价
+        .route("/", post(tj))
+}
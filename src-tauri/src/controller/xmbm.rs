@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::{routing::post, Json, Router};
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::xmbm::{RenumberCommand, RenumberRequest},
+    error::AppError,
+    res,
+    response::Res,
+    utils::xmbm::{dwgc_ids, find_duplicates, XmbmDuplicate},
+    ContextHelper, ResponseResult,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct XmbmPost {
+    pub editor_name: String,
+    /// 单位工程 或 单项工程、工程项目
+    pub id: NodeId,
+}
+
+/// 单位工程内的重复编码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DwgcDuplicates {
+    pub dwgc_id: NodeId,
+    pub duplicates: Vec<XmbmDuplicate>,
+}
+
+/// 节点下各单位工程的重复编码
+fn duplicates_of(doc: &NodePool, id: &NodeId) -> Vec<DwgcDuplicates> {
+    dwgc_ids(doc, id)
+        .into_iter()
+        .map(|dwgc_id| DwgcDuplicates {
+            duplicates: find_duplicates(doc, &dwgc_id),
+            dwgc_id,
+        })
+        .filter(|item| !item.duplicates.is_empty())
+        .collect()
+}
+
+/// 检查重复的清单编码
+pub async fn get_duplicates(Json(param): Json<XmbmPost>) -> ResponseResult<Vec<DwgcDuplicates>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let doc = editor.unwrap().doc().await;
+    res!(duplicates_of(&doc, &param.id))
+}
+
+/// 重排项目编码 返回重排后仍重复的编码
+pub async fn renumber(Json(param): Json<RenumberRequest>) -> ResponseResult<Vec<DwgcDuplicates>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::to_value(param.clone())?;
+    editor
+        .command_with_meta(
+            Arc::new(RenumberCommand {
+                data: param.clone(),
+            }),
+            "重排项目编码".to_string(),
+            meta,
+        )
+        .await?;
+    let doc = editor.doc().await;
+    res!(duplicates_of(&doc, &param.id))
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //重排项目编码
+        .route("/renumber", post(renumber))
+        //检查重复编码
+        .route("/duplicates", post(get_duplicates))
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use axum::{routing::post, Json, Router};
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AppError,
+    nodes::{
+        fbfx_csxm::{DE_STR, QD_STR},
+        rcj::RCJ_STR,
+    },
+    res,
+    response::Res,
+    utils::{
+        money::{get_decimal, Decimal},
+        node::{children_of_type, descendants_of_type, get_str},
+        price::{
+            de_unit_cost_with, is_jgcl, is_zgcl, rcj_base_price, rcj_price, DjgcRowCost,
+            PriceOptions, RcjKind, UnitCost,
+        },
+    },
+    ContextHelper, ResponseResult,
+};
+
+/// 综合单价分析表 定额行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZhdjfxDeRow {
+    pub id: NodeId,
+    pub project_code: String,
+    pub project_name: String,
+    pub unit: String,
+    /// 定额工程量
+    pub quantity: Decimal,
+    /// 含量 = 定额工程量 / 清单工程量
+    pub ratio: Decimal,
+    /// 定额单位价格构成
+    pub unit_cost: UnitCost,
+    /// 定额单价
+    pub price: Decimal,
+    /// 折算到每单位清单工程量的合价 = 定额单价 × 含量
+    pub total: Decimal,
+    /// 单价构成明细
+    pub djgc: Vec<DjgcRowCost>,
+}
+
+/// 综合单价分析表 材料明细行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZhdjfxMaterialRow {
+    pub material_code: String,
+    pub material_name: String,
+    pub specification: String,
+    pub unit: String,
+    pub kind: RcjKind,
+    /// 暂估材料
+    pub zg: bool,
+    /// 甲供材料
+    pub jg: bool,
+    /// 每单位清单工程量的消耗量
+    pub res_qty: Decimal,
+    pub price_base: Decimal,
+    pub price_market: Decimal,
+    /// 计入综合单价的单价 价差单列时为定额基价
+    pub price: Decimal,
+    /// 每单位清单工程量的合价
+    pub total: Decimal,
+}
+
+/// 清单 综合单价分析表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZhdjfxTable {
+    pub id: NodeId,
+    pub project_code: String,
+    pub project_name: String,
+    pub project_attr: String,
+    pub unit: String,
+    pub quantity: Decimal,
+    /// 清单单位价格构成(各定额按含量折算后累加)
+    pub unit_cost: UnitCost,
+    /// 综合单价 按单位工程的单价小数位数取整
+    pub price: Decimal,
+    /// 综合合价 = 综合单价 × 清单工程量 按单位工程的取整规则计算
+    pub total: Decimal,
+    pub de_rows: Vec<ZhdjfxDeRow>,
+    pub materials: Vec<ZhdjfxMaterialRow>,
+}
+
+impl ZhdjfxTable {
+    /// 遍历 qd → de → rcj (及单价构成) 构建清单的综合单价分析表
+    pub fn build(doc: &NodePool, qd_id: &NodeId) -> Option<Self> {
+        let qd = doc.get_node(qd_id)?;
+        if qd.r#type != QD_STR {
+            return None;
+        }
+        let qd_quantity = get_decimal(&qd, "quantity");
+        let options = PriceOptions::of(doc, qd_id);
+        let mut unit_cost = UnitCost::default();
+        let mut de_rows = Vec::new();
+        // 材料明细按 编码 + 单价 合并
+        let mut materials: Vec<ZhdjfxMaterialRow> = Vec::new();
+        let mut material_index: HashMap<(String, String), usize> = HashMap::new();
+        for de in children_of_type(doc, qd_id, DE_STR) {
+            let quantity = get_decimal(&de, "quantity");
+            let ratio = if qd_quantity.is_zero() {
+                Decimal::ZERO
+            } else {
+                quantity / qd_quantity
+            };
+            let (de_cost, djgc) = de_unit_cost_with(doc, &de.id, &options);
+            unit_cost.add_scaled(&de_cost, ratio);
+            for rcj in children_of_type(doc, &de.id, RCJ_STR) {
+                let kind = RcjKind::of(&rcj);
+                if kind == RcjKind::Rg || kind == RcjKind::Jx {
+                    continue;
+                }
+                let price_market = rcj_price(&rcj);
+                let price = options.rcj_price(&rcj);
+                let res_qty = get_decimal(&rcj, "resQty") * ratio;
+                let material_code = get_str(&rcj, "materialCode");
+                let key = (material_code.clone(), price.to_string());
+                match material_index.get(&key) {
+                    Some(index) => {
+                        let row = &mut materials[*index];
+                        row.res_qty += res_qty;
+                        row.total = row.res_qty * row.price;
+                    }
+                    None => {
+                        material_index.insert(key, materials.len());
+                        materials.push(ZhdjfxMaterialRow {
+                            material_code,
+                            material_name: get_str(&rcj, "materialName"),
+                            specification: get_str(&rcj, "specification"),
+                            unit: get_str(&rcj, "unit"),
+                            kind,
+                            zg: is_zgcl(&rcj),
+                            jg: is_jgcl(&rcj),
+                            res_qty,
+                            price_base: rcj_base_price(&rcj),
+                            price_market,
+                            price,
+                            total: res_qty * price,
+                        });
+                    }
+                }
+            }
+            let price = de_cost.price();
+            de_rows.push(ZhdjfxDeRow {
+                id: de.id.clone(),
+                project_code: get_str(&de, "projectCode"),
+                project_name: get_str(&de, "projectName"),
+                unit: get_str(&de, "unit"),
+                quantity,
+                ratio,
+                unit_cost: de_cost,
+                price,
+                total: price * ratio,
+                djgc,
+            });
+        }
+        let rounding = &options.rounding;
+        let unit = get_str(&qd, "unit");
+        let price = unit_cost.price();
+        Some(ZhdjfxTable {
+            id: qd.id.clone(),
+            project_code: get_str(&qd, "projectCode"),
+            project_name: get_str(&qd, "projectName"),
+            project_attr: get_str(&qd, "projectAttr"),
+            quantity: rounding.quantity(&unit, qd_quantity),
+            unit_cost,
+            price: rounding.price(price),
+            total: rounding.amount(price, qd_quantity, &unit),
+            unit,
+            de_rows,
+            materials,
+        })
+    }
+
+    /// 构建节点下所有清单的综合单价分析表 按文档顺序排列
+    pub fn build_all(doc: &NodePool, id: &NodeId) -> Vec<Self> {
+        if let Some(table) = Self::build(doc, id) {
+            return vec![table];
+        }
+        descendants_of_type(doc, id, &[QD_STR])
+            .iter()
+            .filter_map(|qd| Self::build(doc, &qd.id))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZhdjfxPost {
+    pub editor_name: String,
+    pub id: String,
+}
+
+/// 获取单条清单的综合单价分析表
+pub async fn get_qd_zhdjfx(Json(param): Json<ZhdjfxPost>) -> ResponseResult<ZhdjfxTable> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    match ZhdjfxTable::build(&doc, &param.id) {
+        Some(table) => res!(table),
+        None => Err(AppError(anyhow::anyhow!("清单节点不存在".to_string()))),
+    }
+}
+
+/// 获取节点(单位工程、分部分项、措施项目、分部)下所有清单的综合单价分析表
+pub async fn get_zhdjfx_list(Json(param): Json<ZhdjfxPost>) -> ResponseResult<Vec<ZhdjfxTable>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let editor = editor.unwrap();
+    let doc = editor.doc().await;
+    if doc.get_node(&param.id).is_none() {
+        return Err(AppError(anyhow::anyhow!("节点不存在".to_string())));
+    }
+    res!(ZhdjfxTable::build_all(&doc, &param.id))
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //获取清单综合单价分析表
+        .route("/get_qd_zhdjfx", post(get_qd_zhdjfx))
+        //获取节点下所有清单综合单价分析表
+        .route("/get_zhdjfx_list", post(get_zhdjfx_list))
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::{
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::zjfa::{ApplyZjfaCommand, ApplyZjfaRequest, ZjfaLibrary, ZjfaScheme},
+    error::AppError,
+    nodes::fbfx_csxm::QD_STR,
+    res,
+    response::Res,
+    utils::{
+        local_library::{load_library, save_library},
+        node::get_str,
+    },
+    ContextHelper, ResponseResult,
+};
+
+lazy_static! {
+    /// 本地组价方案库 读改写 互斥锁
+    static ref ZJFA_LOCAL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// 读取本地组价方案库快照 持锁读取 避免读到并发保存中的文件
+async fn load_local() -> anyhow::Result<ZjfaLibrary> {
+    let _guard = ZJFA_LOCAL_LOCK.lock().await;
+    load_library(ZjfaLibrary::LOCAL_NAME)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveZjfaPost {
+    pub editor_name: String,
+    /// 来源清单
+    pub qd_id: String,
+    pub name: String,
+}
+
+/// 把清单的定额组成保存为组价方案
+pub async fn save_zjfa(Json(param): Json<SaveZjfaPost>) -> ResponseResult<ZjfaScheme> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let doc = editor.unwrap().doc().await;
+    let scheme = ZjfaScheme::from_qd(&doc, &param.qd_id, param.name)?;
+    let _guard = ZJFA_LOCAL_LOCK.lock().await;
+    let mut library: ZjfaLibrary = load_library(ZjfaLibrary::LOCAL_NAME)?;
+    library.schemes.push(scheme.clone());
+    save_library(ZjfaLibrary::LOCAL_NAME, &library)?;
+    res!(scheme)
+}
+
+/// 获取本地组价方案列表
+pub async fn get_zjfa_list() -> ResponseResult<Vec<ZjfaScheme>> {
+    let library = load_local().await?;
+    res!(library.schemes)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteZjfaPost {
+    pub id: String,
+}
+
+/// 删除组价方案
+pub async fn delete_zjfa(Json(param): Json<DeleteZjfaPost>) -> ResponseResult<String> {
+    let _guard = ZJFA_LOCAL_LOCK.lock().await;
+    let mut library: ZjfaLibrary = load_library(ZjfaLibrary::LOCAL_NAME)?;
+    library.schemes.retain(|s| s.id != param.id);
+    save_library(ZjfaLibrary::LOCAL_NAME, &library)?;
+    res!("删除成功".to_string())
+}
+
+/// 应用组价方案到清单
+pub async fn apply_zjfa(Json(param): Json<ApplyZjfaRequest>) -> ResponseResult<String> {
+    let library = load_local().await?;
+    let scheme = library.get(&param.scheme_id);
+    if scheme.is_none() {
+        return Err(AppError(anyhow::anyhow!("组价方案不存在".to_string())));
+    }
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let mut editor = editor.unwrap();
+    let meta = serde_json::json!({
+        "name": scheme.unwrap().name.clone(),
+        "count": param.qd_ids.len(),
+    });
+    editor
+        .command_with_meta(
+            Arc::new(ApplyZjfaCommand {
+                data: param.clone(),
+                scheme: scheme.unwrap().clone(),
+            }),
+            "应用组价方案 {{name}} 到 {{count}} 条清单".to_string(),
+            meta,
+        )
+        .await?;
+    res!("success".to_string())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchZjfaPost {
+    pub editor_name: String,
+    pub qd_id: String,
+    /// 返回的最大候选数 默认 5
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZjfaMatch {
+    pub score: f64,
+    pub scheme: ZjfaScheme,
+}
+
+/// 智能匹配 按项目编码前缀与项目特征相似度推荐组价方案
+pub async fn match_zjfa(Json(param): Json<MatchZjfaPost>) -> ResponseResult<Vec<ZjfaMatch>> {
+    let editor = ContextHelper::get_editor(&param.editor_name);
+    if editor.is_none() {
+        return Err(AppError(anyhow::anyhow!("工程项目不存在".to_string())));
+    }
+    let doc = editor.unwrap().doc().await;
+    let qd = match doc.get_node(&param.qd_id) {
+        Some(qd) if qd.r#type == QD_STR => qd,
+        _ => return Err(AppError(anyhow::anyhow!("清单节点不存在".to_string()))),
+    };
+    let project_code = get_str(&qd, "projectCode");
+    let project_attr = get_str(&qd, "projectAttr");
+    let library = load_local().await?;
+    let mut matches: Vec<ZjfaMatch> = library
+        .schemes
+        .into_iter()
+        .map(|scheme| ZjfaMatch {
+            score: scheme.match_score(&project_code, &project_attr),
+            scheme,
+        })
+        .filter(|m| m.score > 0.0)
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(param.limit.unwrap_or(5));
+    res!(matches)
+}
+
+pub fn build_app() -> Router {
+    Router::new()
+        //保存组价方案
+        .route("/save_zjfa", post(save_zjfa))
+        //获取组价方案列表
+        .route("/get_zjfa_list", get(get_zjfa_list))
+        //删除组价方案
+        .route("/delete_zjfa", post(delete_zjfa))
+        //应用组价方案
+        .route("/apply_zjfa", post(apply_zjfa))
+        //智能匹配组价方案
+        .route("/match_zjfa", post(match_zjfa))
+}
@@ -1,12 +1,24 @@
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
 
+use crate::{nodes::field::FieldErrors, response::Res};
+
 pub struct AppError(pub anyhow::Error);
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        // 属性校验失败 返回逐个字段的错误
+        if let Some(errors) = self.0.downcast_ref::<FieldErrors>() {
+            let body = Res {
+                code: 422,
+                msg: Some(errors.to_string()),
+                data: Some(errors.0.clone()),
+            };
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+        }
         (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", self.0)).into_response()
     }
 }
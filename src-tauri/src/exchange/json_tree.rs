@@ -0,0 +1,274 @@
+//! 工程数据 JSON 树 用于技术支持、问题复现及与其他系统交换
+//!
+//! 文件结构:
+//! ```json
+//! {
+//!   "format": "moduforge-gcxm-tree",
+//!   "version": 1,
+//!   "exportedAt": "2025-01-01 12:00:00",
+//!   "root": {
+//!     "id": "节点 id",
+//!     "type": "DWGC",
+//!     "attrs": { "name": "单位工程" },
+//!     "marks": [],
+//!     "children": [ ... ]
+//!   }
+//! }
+//! ```
+//! 节点结构与 GcxmTreeItem 一致 attrs 按键名排序 便于比较差异
+//! 导入时按 init_extension 构建的 schema 校验节点类型、子节点、属性与标记
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::Local;
+use mf_model::{
+    id_generator::IdGenerator, mark::Mark, node::Node, node_pool::NodePool, schema::Schema,
+    types::NodeId,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{controller::GcxmTreeItem, exchange::ExchangeIssue};
+
+/// 格式标识
+pub const JSON_TREE_FORMAT: &str = "moduforge-gcxm-tree";
+/// 格式版本 结构不兼容调整时递增
+pub const JSON_TREE_VERSION: u32 = 1;
+/// 引用节点 id 的属性 重新生成 id 时按新 id 替换 其余属性原样保留
+const REFERENCE_ATTRS: [&str; 2] = ["deId", "constructId"];
+
+/// JSON 树节点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonTreeNode {
+    pub id: NodeId,
+    pub r#type: String,
+    #[serde(default)]
+    pub attrs: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub marks: Vec<Mark>,
+    #[serde(default)]
+    pub children: Vec<JsonTreeNode>,
+}
+
+/// JSON 树文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTreeDocument {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: String,
+    pub root: JsonTreeNode,
+}
+
+/// 导入前的校验结果 errors 不为空时不能导入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonTreeCheck {
+    pub errors: Vec<ExchangeIssue>,
+    /// 已忽略的未知属性
+    pub warnings: Vec<ExchangeIssue>,
+}
+
+impl From<&GcxmTreeItem> for JsonTreeNode {
+    fn from(item: &GcxmTreeItem) -> Self {
+        let attrs = match serde_json::to_value(&item.attrs) {
+            Ok(Value::Object(map)) => map.into_iter().collect(),
+            _ => BTreeMap::new(),
+        };
+        Self {
+            id: item.id.clone(),
+            r#type: item.r#type.clone(),
+            attrs,
+            marks: item.marks.clone(),
+            children: item.children.iter().map(JsonTreeNode::from).collect(),
+        }
+    }
+}
+
+impl JsonTreeNode {
+    /// 子树节点数(含自身)
+    pub fn count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(|child| child.count())
+            .sum::<usize>()
+    }
+
+    /// 属性 HashMap 形式 用于创建节点
+    pub fn attrs_map(&self) -> HashMap<String, Value> {
+        self.attrs
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    fn collect_ids(&self, ids: &mut Vec<NodeId>) {
+        ids.push(self.id.clone());
+        for child in self.children.iter() {
+            child.collect_ids(ids);
+        }
+    }
+
+    fn replace_ids(&mut self, mapping: &HashMap<NodeId, NodeId>) {
+        if let Some(id) = mapping.get(&self.id) {
+            self.id = id.clone();
+        }
+        // 引用子树内节点 id 的属性一并替换
+        for key in REFERENCE_ATTRS.iter() {
+            if let Some(Value::String(s)) = self.attrs.get_mut(*key) {
+                if let Some(id) = mapping.get(s.as_str()) {
+                    *s = id.clone();
+                }
+            }
+        }
+        for child in self.children.iter_mut() {
+            child.replace_ids(mapping);
+        }
+    }
+
+    /// 重新生成子树内全部 id
+    pub fn regenerate_ids(&mut self) {
+        let mut ids = Vec::new();
+        self.collect_ids(&mut ids);
+        let mapping: HashMap<NodeId, NodeId> = ids
+            .into_iter()
+            .map(|id| (id, IdGenerator::get_id()))
+            .collect();
+        self.replace_ids(&mapping);
+    }
+
+    /// 检查保留原 id 导入时是否与文档或文件内其他节点冲突
+    pub fn check_ids(&self, doc: &NodePool, errors: &mut Vec<ExchangeIssue>) {
+        let mut ids = Vec::new();
+        self.collect_ids(&mut ids);
+        let mut seen = HashSet::new();
+        for id in ids {
+            if !seen.insert(id.clone()) {
+                errors.push(ExchangeIssue {
+                    path: id.clone(),
+                    message: "文件中节点 id 重复".to_string(),
+                });
+            } else if doc.get_node(&id).is_some() {
+                errors.push(ExchangeIssue {
+                    path: id.clone(),
+                    message: "节点 id 已存在 请选择重新生成 id".to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// 按文档顺序导出子树
+pub fn export_tree(doc: &NodePool, id: &NodeId) -> anyhow::Result<JsonTreeDocument> {
+    let item = GcxmTreeItem::from_doc(doc, id, &|_: &Node| true)
+        .ok_or_else(|| anyhow::anyhow!("节点不存在".to_string()))?;
+    Ok(JsonTreeDocument {
+        format: JSON_TREE_FORMAT.to_string(),
+        version: JSON_TREE_VERSION,
+        exported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        root: JsonTreeNode::from(&item),
+    })
+}
+
+/// 解析 JSON 树文件
+pub fn parse_tree(content: &[u8]) -> anyhow::Result<JsonTreeDocument> {
+    let document: JsonTreeDocument = serde_json::from_slice(content)
+        .map_err(|e| anyhow::anyhow!("JSON 树文件解析失败: {}", e))?;
+    if document.format != JSON_TREE_FORMAT {
+        return Err(anyhow::anyhow!("不支持的文件格式 {}", document.format));
+    }
+    if document.version > JSON_TREE_VERSION {
+        return Err(anyhow::anyhow!(
+            "文件版本 {} 高于当前支持的版本 {}",
+            document.version,
+            JSON_TREE_VERSION
+        ));
+    }
+    Ok(document)
+}
+
+/// 内容表达式中出现的节点类型或分组名
+fn content_names(content: &str) -> Vec<&str> {
+    content
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// 子节点类型是否满足父节点内容表达式 名称为节点类型或其所属分组
+pub fn content_allows(schema: &Schema, content: &str, child_type: &str) -> bool {
+    let groups: Vec<String> = schema
+        .nodes
+        .get(child_type)
+        .and_then(|node_type| node_type.spec.group.clone())
+        .map(|group| group.split_whitespace().map(|g| g.to_string()).collect())
+        .unwrap_or_default();
+    content_names(content)
+        .iter()
+        .any(|name| *name == child_type || groups.iter().any(|g| g == name))
+}
+
+/// 按 schema 校验子树 并去除 schema 中未定义的属性
+/// parent_type 为导入位置的节点类型 导入为新工程项目时为空
+pub fn check_tree(
+    schema: &Schema,
+    parent_type: Option<&str>,
+    node: &mut JsonTreeNode,
+    path: &str,
+    check: &mut JsonTreeCheck,
+) {
+    let path = if path.is_empty() {
+        format!("{}[{}]", node.r#type, node.id)
+    } else {
+        format!("{}/{}[{}]", path, node.r#type, node.id)
+    };
+    let node_type = match schema.nodes.get(&node.r#type) {
+        Some(node_type) => node_type,
+        None => {
+            check.errors.push(ExchangeIssue {
+                path,
+                message: format!("节点类型 {} 不存在", node.r#type),
+            });
+            return;
+        }
+    };
+    if let Some(parent_type) = parent_type {
+        let content = schema
+            .nodes
+            .get(parent_type)
+            .and_then(|parent| parent.spec.content.clone())
+            .unwrap_or_default();
+        if !content_allows(schema, &content, &node.r#type) {
+            check.errors.push(ExchangeIssue {
+                path: path.clone(),
+                message: format!("{} 下不能包含 {}", parent_type, node.r#type),
+            });
+        }
+    }
+    let known = node_type.spec.attrs.clone().unwrap_or_default();
+    let unknown: Vec<String> = node
+        .attrs
+        .keys()
+        .filter(|key| !known.contains_key(*key))
+        .cloned()
+        .collect();
+    for key in unknown {
+        node.attrs.remove(&key);
+        check.warnings.push(ExchangeIssue {
+            path: path.clone(),
+            message: format!("忽略未定义的属性 {}", key),
+        });
+    }
+    for mark in node.marks.iter() {
+        if !schema.marks.contains_key(&mark.r#type) {
+            check.errors.push(ExchangeIssue {
+                path: path.clone(),
+                message: format!("标记类型 {} 不存在", mark.r#type),
+            });
+        }
+    }
+    let node_type_name = node.r#type.clone();
+    for child in node.children.iter_mut() {
+        check_tree(schema, Some(&node_type_name), child, &path, check);
+    }
+}
@@ -0,0 +1,12 @@
+// 数据交换 工程数据与 JSON 树文件互转
+use serde::{Deserialize, Serialize};
+
+pub mod json_tree;
+
+/// 校验、导入时发现的问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeIssue {
+    /// 问题位置 例如 单位工程/分部分项/清单 010101001001
+    pub path: String,
+    pub message: String,
+}
@@ -0,0 +1,2 @@
+// 导出 Excel 等离线文件
+pub mod xlsx;
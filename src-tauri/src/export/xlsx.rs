@@ -0,0 +1,481 @@
+use std::collections::HashSet;
+
+use mf_model::{node_pool::NodePool, types::NodeId};
+use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, Workbook, Worksheet};
+
+use crate::{
+    controller::{rcj::collect_rcj_hz, GcxmTreeItem},
+    nodes::{
+        column::{custom_columns, CustomColumn},
+        fbfx_csxm::{CSXM_STR, FBFX_STR, FB_STR, QD_STR},
+        gcxm::DWGC_STR,
+    },
+    utils::{
+        fyhz::{Fyhz, XmhzItem},
+        money::{get_decimal, to_f64, Decimal},
+        node::{children_of_type, descendants_of_type, get_str},
+        price::{qd_amount, PriceOptions},
+    },
+};
+
+/// Excel 文件 MIME 类型
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// 计价表列宽 序号、编码、名称、特征、单位、工程量、单价、合价、暂估价
+const COLUMN_WIDTHS: [f64; 9] = [6.0, 16.0, 24.0, 32.0, 8.0, 12.0, 12.0, 14.0, 12.0];
+
+/// 计价表自定义列列宽
+const CUSTOM_COLUMN_WIDTH: f64 = 14.0;
+
+/// 表格样式
+struct Formats {
+    title: Format,
+    header: Format,
+    text: Format,
+    text_bold: Format,
+    quantity: Format,
+    money: Format,
+    money_bold: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        let cell = Format::new()
+            .set_border(FormatBorder::Thin)
+            .set_align(FormatAlign::VerticalCenter);
+        Self {
+            title: Format::new()
+                .set_bold()
+                .set_font_size(16)
+                .set_align(FormatAlign::Center)
+                .set_align(FormatAlign::VerticalCenter),
+            header: cell
+                .clone()
+                .set_bold()
+                .set_align(FormatAlign::Center)
+                .set_text_wrap(),
+            text: cell.clone().set_text_wrap(),
+            text_bold: cell.clone().set_bold().set_text_wrap(),
+            quantity: cell.clone().set_num_format("0.000"),
+            money: cell.clone().set_num_format("#,##0.00"),
+            money_bold: cell.set_bold().set_num_format("#,##0.00"),
+        }
+    }
+}
+
+/// 按行写入的工作表
+struct SheetWriter<'a> {
+    sheet: &'a mut Worksheet,
+    formats: &'a Formats,
+    row: u32,
+}
+
+impl<'a> SheetWriter<'a> {
+    fn new(sheet: &'a mut Worksheet, formats: &'a Formats) -> anyhow::Result<Self> {
+        for (col, width) in COLUMN_WIDTHS.iter().enumerate() {
+            sheet.set_column_width(col as u16, *width)?;
+        }
+        Ok(Self {
+            sheet,
+            formats,
+            row: 0,
+        })
+    }
+
+    /// 表标题及工程名称
+    fn title(&mut self, title: &str, project_name: &str, last_col: u16) -> anyhow::Result<()> {
+        self.sheet.set_row_height(self.row, 28)?;
+        self.sheet
+            .merge_range(self.row, 0, self.row, last_col, title, &self.formats.title)?;
+        self.row += 1;
+        self.sheet.merge_range(
+            self.row,
+            0,
+            self.row,
+            last_col,
+            &format!("工程名称：{}", project_name),
+            &Format::new(),
+        )?;
+        self.row += 1;
+        Ok(())
+    }
+
+    /// 单行表头
+    fn header(&mut self, columns: &[&str]) -> anyhow::Result<()> {
+        for (col, name) in columns.iter().enumerate() {
+            self.sheet.write_string_with_format(
+                self.row,
+                col as u16,
+                *name,
+                &self.formats.header,
+            )?;
+        }
+        self.row += 1;
+        Ok(())
+    }
+
+    fn text(&mut self, col: u16, value: &str, bold: bool) -> anyhow::Result<()> {
+        let format = if bold {
+            &self.formats.text_bold
+        } else {
+            &self.formats.text
+        };
+        self.sheet
+            .write_string_with_format(self.row, col, value, format)?;
+        Ok(())
+    }
+
+    fn quantity(&mut self, col: u16, value: f64) -> anyhow::Result<()> {
+        self.sheet
+            .write_number_with_format(self.row, col, value, &self.formats.quantity)?;
+        Ok(())
+    }
+
+    fn money(&mut self, col: u16, value: f64, bold: bool) -> anyhow::Result<()> {
+        let format = if bold {
+            &self.formats.money_bold
+        } else {
+            &self.formats.money
+        };
+        self.sheet
+            .write_number_with_format(self.row, col, value, format)?;
+        Ok(())
+    }
+
+    /// 小计、合计行 名称合并 序号至计量单位 列
+    fn total_row(&mut self, name: &str, total: Decimal, zg_total: Decimal) -> anyhow::Result<()> {
+        self.sheet
+            .merge_range(self.row, 0, self.row, 6, name, &self.formats.text_bold)?;
+        self.money(7, to_f64(total), true)?;
+        self.money(8, to_f64(zg_total), true)?;
+        self.row += 1;
+        Ok(())
+    }
+
+    fn skip(&mut self, rows: u32) {
+        self.row += rows;
+    }
+}
+
+/// 分部分项、措施项目 计价表
+/// 表头两行 金额(元) 合并在 综合单价、合价、其中暂估价 上方 每个分部后输出 分部小计
+/// 工程项目的自定义列依次输出在 其中：暂估价 之后
+fn write_qd_table(
+    writer: &mut SheetWriter,
+    doc: &NodePool,
+    dwgc_id: &NodeId,
+    root_type: &str,
+    title: &str,
+) -> anyhow::Result<()> {
+    let dwgc = match doc.get_node(dwgc_id) {
+        Some(dwgc) => dwgc,
+        None => return Ok(()),
+    };
+    let custom = custom_columns(doc);
+    writer.title(title, &get_str(&dwgc, "name"), 8 + custom.len() as u16)?;
+    let header = writer.formats.header.clone();
+    let row = writer.row;
+    let columns = [
+        "序号",
+        "项目编码",
+        "项目名称",
+        "项目特征描述",
+        "计量单位",
+        "工程量",
+    ];
+    for (col, name) in columns.iter().enumerate() {
+        writer
+            .sheet
+            .merge_range(row, col as u16, row + 1, col as u16, name, &header)?;
+    }
+    writer
+        .sheet
+        .merge_range(row, 6, row, 8, "金额(元)", &header)?;
+    for (col, name) in ["综合单价", "合价", "其中：暂估价"].iter().enumerate() {
+        writer
+            .sheet
+            .write_string_with_format(row + 1, 6 + col as u16, *name, &header)?;
+    }
+    for (index, column) in custom.iter().enumerate() {
+        let col = 9 + index as u16;
+        writer.sheet.set_column_width(col, CUSTOM_COLUMN_WIDTH)?;
+        writer
+            .sheet
+            .merge_range(row, col, row + 1, col, &column.label, &header)?;
+    }
+    writer.skip(2);
+
+    let options = PriceOptions::of(doc, dwgc_id);
+    let mut seq = 0;
+    let mut total = (Decimal::ZERO, Decimal::ZERO);
+    for root in children_of_type(doc, dwgc_id, root_type) {
+        if let Some(tree) = GcxmTreeItem::fbfx_csxm_tree(doc, &root.id) {
+            let sub = write_qd_rows(writer, doc, &tree, &options, &custom, &mut seq)?;
+            total.0 += sub.0;
+            total.1 += sub.1;
+        }
+    }
+    writer.total_row("合计", total.0, total.1)
+}
+
+/// 输出分部、清单行 返回 (合价, 暂估价) 合计
+fn write_qd_rows(
+    writer: &mut SheetWriter,
+    doc: &NodePool,
+    item: &GcxmTreeItem,
+    options: &PriceOptions,
+    custom: &[CustomColumn],
+    seq: &mut usize,
+) -> anyhow::Result<(Decimal, Decimal)> {
+    let mut total = (Decimal::ZERO, Decimal::ZERO);
+    for child in item.children.iter() {
+        let node = match doc.get_node(&child.id) {
+            Some(node) => node,
+            None => continue,
+        };
+        if child.r#type == FB_STR {
+            writer.text(0, "", true)?;
+            writer.text(1, &get_str(&node, "projectCode"), true)?;
+            writer.text(2, &get_str(&node, "projectName"), true)?;
+            for col in 3..9 {
+                writer.text(col, "", true)?;
+            }
+            for (index, column) in custom.iter().enumerate() {
+                writer.text(9 + index as u16, &column.text(&node), true)?;
+            }
+            writer.skip(1);
+            let sub = write_qd_rows(writer, doc, child, options, custom, seq)?;
+            writer.total_row("分部小计", sub.0, sub.1)?;
+            total.0 += sub.0;
+            total.1 += sub.1;
+        } else if child.r#type == QD_STR {
+            *seq += 1;
+            let amount = qd_amount(doc, &node, options);
+            let zg_total = get_decimal(&node, "zgfTotal");
+            writer.text(0, &seq.to_string(), false)?;
+            writer.text(1, &get_str(&node, "projectCode"), false)?;
+            writer.text(2, &get_str(&node, "projectName"), false)?;
+            writer.text(3, &get_str(&node, "projectAttr"), false)?;
+            writer.text(4, &get_str(&node, "unit"), false)?;
+            writer.quantity(5, to_f64(amount.quantity))?;
+            writer.money(6, to_f64(amount.price), false)?;
+            writer.money(7, to_f64(amount.total), false)?;
+            writer.money(8, to_f64(zg_total), false)?;
+            for (index, column) in custom.iter().enumerate() {
+                writer.text(9 + index as u16, &column.text(&node), false)?;
+            }
+            writer.skip(1);
+            total.0 += amount.total;
+            total.1 += zg_total;
+        }
+    }
+    Ok(total)
+}
+
+/// 人材机汇总表
+fn write_rcj_table(
+    writer: &mut SheetWriter,
+    doc: &NodePool,
+    dwgc_id: &NodeId,
+) -> anyhow::Result<()> {
+    let dwgc = match doc.get_node(dwgc_id) {
+        Some(dwgc) => dwgc,
+        None => return Ok(()),
+    };
+    writer.title("人材机汇总表", &get_str(&dwgc, "name"), 8)?;
+    writer.header(&[
+        "序号",
+        "材料编码",
+        "名称",
+        "规格型号",
+        "单位",
+        "数量",
+        "定额价",
+        "市场价",
+        "合价",
+    ])?;
+    let rows = collect_rcj_hz(doc, dwgc_id, |_| true);
+    let mut total = Decimal::ZERO;
+    for (index, row) in rows.iter().enumerate() {
+        writer.text(0, &(index + 1).to_string(), false)?;
+        writer.text(1, &row.material_code, false)?;
+        writer.text(2, &row.material_name, false)?;
+        writer.text(3, &row.specification, false)?;
+        writer.text(4, &row.unit, false)?;
+        writer.quantity(5, to_f64(row.quantity))?;
+        writer.money(6, to_f64(row.price_base), false)?;
+        writer.money(7, to_f64(row.price_market), false)?;
+        writer.money(8, to_f64(row.total), false)?;
+        writer.skip(1);
+        total += row.total;
+    }
+    writer.sheet.merge_range(
+        writer.row,
+        0,
+        writer.row,
+        7,
+        "合计",
+        &writer.formats.text_bold,
+    )?;
+    writer.money(8, to_f64(total), true)?;
+    writer.skip(1);
+    Ok(())
+}
+
+/// 费用汇总表
+fn write_fyhz_table(
+    writer: &mut SheetWriter,
+    doc: &NodePool,
+    dwgc_id: &NodeId,
+) -> anyhow::Result<()> {
+    let fyhz = match Fyhz::build(doc, dwgc_id) {
+        Some(fyhz) => fyhz,
+        None => return Ok(()),
+    };
+    writer.title("单位工程费用汇总表", &fyhz.name, 4)?;
+    writer.header(&["序号", "费用代号", "费用名称", "计算基数", "金额(元)"])?;
+    for (index, row) in fyhz.rows.iter().enumerate() {
+        writer.text(0, &(index + 1).to_string(), false)?;
+        writer.text(1, &row.code, false)?;
+        writer.text(2, &row.name, false)?;
+        writer.text(3, &row.base, false)?;
+        writer.money(4, to_f64(row.amount), false)?;
+        writer.skip(1);
+    }
+    Ok(())
+}
+
+/// 工程项目汇总表 单项工程行为下属单项、单位工程小计
+fn write_xmhz_table(writer: &mut SheetWriter, doc: &NodePool, id: &NodeId) -> anyhow::Result<()> {
+    let xmhz = match XmhzItem::build(doc, id) {
+        Some(xmhz) => xmhz,
+        None => return Ok(()),
+    };
+    writer.title("工程项目汇总表", &xmhz.name, 8)?;
+    writer.header(&[
+        "序号",
+        "单项、单位工程名称",
+        "分部分项工程费",
+        "措施项目费",
+        "其他项目费",
+        "规费",
+        "税金",
+        "合计(元)",
+        "占比(%)",
+    ])?;
+    for (index, row) in xmhz.flatten().iter().enumerate() {
+        let bold = row.level == 0;
+        writer.text(0, &(index + 1).to_string(), bold)?;
+        writer.text(1, &format!("{}{}", "  ".repeat(row.level), row.name), bold)?;
+        for (col, amount) in [
+            row.fbfx, row.csxm, row.qtxm, row.gf, row.sj, row.total, row.share,
+        ]
+        .iter()
+        .enumerate()
+        {
+            writer.money(2 + col as u16, to_f64(*amount), bold)?;
+        }
+        writer.skip(1);
+    }
+    Ok(())
+}
+
+/// 工作表名称 去除 Excel 不允许的字符 限制 31 个字符 重名时追加序号
+fn sheet_name(name: &str, used: &mut HashSet<String>) -> String {
+    let clean: String = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .collect();
+    let clean = if clean.trim().is_empty() {
+        "单位工程".to_string()
+    } else {
+        clean
+    };
+    // 序号计入长度 名称按剩余长度截取
+    let with_suffix = |suffix: String| -> String {
+        let len = 31 - suffix.chars().count();
+        clean.chars().take(len).chain(suffix.chars()).collect()
+    };
+    let mut result = with_suffix(String::new());
+    let mut index = 1;
+    while used.contains(&result) {
+        index += 1;
+        result = with_suffix(format!("({})", index));
+    }
+    used.insert(result.clone());
+    result
+}
+
+/// 生成 Excel 工作簿
+/// 单位工程 每张表一个工作表
+/// 工程项目、单项工程 首个工作表为工程项目汇总表 之后每个单位工程一个工作表 各表依次排列
+/// 返回 (文件名, 文件内容)
+pub fn export_xlsx(doc: &NodePool, id: &NodeId) -> anyhow::Result<(String, Vec<u8>)> {
+    let node = match doc.get_node(id) {
+        Some(node) => node,
+        None => return Err(anyhow::anyhow!("节点不存在".to_string())),
+    };
+    let formats = Formats::new();
+    let mut workbook = Workbook::new();
+    if node.r#type == DWGC_STR {
+        let mut sheet = Worksheet::new();
+        sheet.set_name("分部分项工程量清单计价表")?;
+        let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+        write_qd_table(&mut writer, doc, id, FBFX_STR, "分部分项工程量清单计价表")?;
+        workbook.push_worksheet(sheet);
+
+        let mut sheet = Worksheet::new();
+        sheet.set_name("措施项目清单计价表")?;
+        let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+        write_qd_table(&mut writer, doc, id, CSXM_STR, "措施项目清单计价表")?;
+        workbook.push_worksheet(sheet);
+
+        let mut sheet = Worksheet::new();
+        sheet.set_name("人材机汇总表")?;
+        let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+        write_rcj_table(&mut writer, doc, id)?;
+        workbook.push_worksheet(sheet);
+
+        let mut sheet = Worksheet::new();
+        sheet.set_name("费用汇总表")?;
+        let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+        write_fyhz_table(&mut writer, doc, id)?;
+        workbook.push_worksheet(sheet);
+    } else {
+        let dwgcs = descendants_of_type(doc, id, &[DWGC_STR]);
+        if dwgcs.is_empty() {
+            return Err(anyhow::anyhow!("没有可导出的单位工程".to_string()));
+        }
+        let mut used = HashSet::new();
+        let mut sheet = Worksheet::new();
+        sheet.set_name(sheet_name("工程项目汇总表", &mut used))?;
+        let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+        write_xmhz_table(&mut writer, doc, id)?;
+        workbook.push_worksheet(sheet);
+        for dwgc in dwgcs {
+            let mut sheet = Worksheet::new();
+            sheet.set_name(sheet_name(&get_str(&dwgc, "name"), &mut used))?;
+            let mut writer = SheetWriter::new(&mut sheet, &formats)?;
+            write_qd_table(
+                &mut writer,
+                doc,
+                &dwgc.id,
+                FBFX_STR,
+                "分部分项工程量清单计价表",
+            )?;
+            writer.skip(2);
+            write_qd_table(&mut writer, doc, &dwgc.id, CSXM_STR, "措施项目清单计价表")?;
+            writer.skip(2);
+            write_rcj_table(&mut writer, doc, &dwgc.id)?;
+            writer.skip(2);
+            write_fyhz_table(&mut writer, doc, &dwgc.id)?;
+            workbook.push_worksheet(sheet);
+        }
+    }
+    let mut file_name = get_str(&node, "name");
+    if file_name.trim().is_empty() {
+        file_name = "工程项目".to_string();
+    }
+    Ok((format!("{}.xlsx", file_name), workbook.save_to_buffer()?))
+}
@@ -0,0 +1,3 @@
+// 导入 Excel、CSV 等外部文件
+pub mod qd;
+pub mod table;
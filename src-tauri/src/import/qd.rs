@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    nodes::fbfx_csxm::{FB_STR, QD_STR},
+    utils::money::{decimal_value, Decimal},
+};
+
+/// 列映射 列号从 0 开始
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QdColumnMapping {
+    /// 编码
+    pub code: Option<usize>,
+    /// 名称
+    pub name: usize,
+    /// 项目特征
+    pub attr: Option<usize>,
+    /// 单位
+    pub unit: Option<usize>,
+    /// 工程量
+    pub quantity: Option<usize>,
+    /// 第一行数据所在行号(之前为表头)
+    #[serde(default)]
+    pub start_row: usize,
+}
+
+impl QdColumnMapping {
+    /// 根据表头文字猜测列映射 取前 10 行中第一行包含 "名称" 的行作为表头
+    pub fn guess(rows: &[Vec<String>]) -> Option<Self> {
+        for (index, row) in rows.iter().take(10).enumerate() {
+            let find = |keys: &[&str]| {
+                row.iter()
+                    .position(|cell| keys.iter().any(|key| cell.contains(key)))
+            };
+            if let Some(name) = find(&["名称"]) {
+                return Some(Self {
+                    code: find(&["编码", "编号"]),
+                    name,
+                    attr: find(&["特征"]),
+                    unit: find(&["单位"]),
+                    quantity: find(&["工程量", "数量"]),
+                    start_row: index + 1,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// 行识别结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QdRowKind {
+    /// 分部标题行
+    Fb,
+    /// 清单行
+    Qd,
+    /// 空行、小计合计行 忽略
+    Skip,
+    /// 无法识别
+    Unknown,
+}
+
+/// 导入行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QdImportRow {
+    /// 文件中的行号 从 1 开始
+    pub row: usize,
+    pub kind: QdRowKind,
+    pub code: String,
+    pub name: String,
+    pub attr: String,
+    pub unit: String,
+    pub quantity: String,
+    /// 无法识别的原因
+    pub message: Option<String>,
+}
+
+/// 待创建的 分部/清单 树
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QdImportNode {
+    pub r#type: String,
+    pub attrs: HashMap<String, Value>,
+    #[serde(default)]
+    pub children: Vec<QdImportNode>,
+}
+
+/// 导入计划
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QdImportPlan {
+    pub fb_count: usize,
+    pub qd_count: usize,
+    pub rows: Vec<QdImportRow>,
+    pub nodes: Vec<QdImportNode>,
+}
+
+impl QdImportPlan {
+    /// 无法识别的行
+    pub fn unknown_rows(&self) -> Vec<&QdImportRow> {
+        self.rows
+            .iter()
+            .filter(|row| row.kind == QdRowKind::Unknown)
+            .collect()
+    }
+}
+
+/// 清单编码 前 9 位为数字(12 位编码或 9 位编码)
+fn is_qd_code(code: &str) -> bool {
+    code.chars().count() >= 9 && code.chars().take(9).all(|c| c.is_ascii_digit())
+}
+
+/// 识别一行
+fn classify(row: &QdImportRow) -> (QdRowKind, Option<String>) {
+    let blank = row.code.is_empty()
+        && row.name.is_empty()
+        && row.attr.is_empty()
+        && row.unit.is_empty()
+        && row.quantity.is_empty();
+    if blank {
+        return (QdRowKind::Skip, None);
+    }
+    if row.code.is_empty() && (row.name.contains("合计") || row.name.contains("小计")) {
+        return (QdRowKind::Skip, None);
+    }
+    if row.name.is_empty() {
+        return (QdRowKind::Unknown, Some("缺少名称".to_string()));
+    }
+    if is_qd_code(&row.code) {
+        if !row.quantity.is_empty() && row.quantity.parse::<Decimal>().is_err() {
+            return (
+                QdRowKind::Unknown,
+                Some(format!("工程量 {} 不是数字", row.quantity)),
+            );
+        }
+        return (QdRowKind::Qd, None);
+    }
+    if row.unit.is_empty() && row.quantity.is_empty() {
+        return (QdRowKind::Fb, None);
+    }
+    (
+        QdRowKind::Unknown,
+        Some("有单位或工程量但编码不是清单编码".to_string()),
+    )
+}
+
+/// 分部层级 编码为数字时 后一分部编码以前一分部编码开头视为其子分部
+fn is_child_fb(parent_code: &str, code: &str) -> bool {
+    !parent_code.is_empty() && code.len() > parent_code.len() && code.starts_with(parent_code)
+}
+
+/// 根据列映射解析表格 生成导入计划
+pub fn build_plan(rows: &[Vec<String>], mapping: &QdColumnMapping) -> QdImportPlan {
+    let cell = |row: &Vec<String>, col: Option<usize>| {
+        col.and_then(|col| row.get(col))
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut plan = QdImportPlan::default();
+    // 当前分部路径 (编码, 在树中的位置)
+    let mut stack: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, row) in rows.iter().enumerate().skip(mapping.start_row) {
+        let mut item = QdImportRow {
+            row: index + 1,
+            kind: QdRowKind::Skip,
+            code: cell(row, mapping.code),
+            name: cell(row, Some(mapping.name)),
+            attr: cell(row, mapping.attr),
+            unit: cell(row, mapping.unit),
+            quantity: cell(row, mapping.quantity),
+            message: None,
+        };
+        let (kind, message) = classify(&item);
+        item.kind = kind;
+        item.message = message;
+        match kind {
+            QdRowKind::Fb => {
+                while let Some((code, _)) = stack.last() {
+                    if is_child_fb(code, &item.code) {
+                        break;
+                    }
+                    stack.pop();
+                }
+                let node = QdImportNode {
+                    r#type: FB_STR.to_string(),
+                    attrs: HashMap::from([
+                        ("projectCode".to_string(), item.code.clone().into()),
+                        ("projectName".to_string(), item.name.clone().into()),
+                    ]),
+                    children: vec![],
+                };
+                let path = push_node(&mut plan.nodes, stack.last().map(|(_, p)| p), node);
+                stack.push((item.code.clone(), path));
+                plan.fb_count += 1;
+            }
+            QdRowKind::Qd => {
+                let mut attrs = HashMap::from([
+                    ("projectCode".to_string(), item.code.clone().into()),
+                    ("projectName".to_string(), item.name.clone().into()),
+                    ("projectAttr".to_string(), item.attr.clone().into()),
+                    ("unit".to_string(), item.unit.clone().into()),
+                ]);
+                if let Ok(quantity) = item.quantity.parse::<Decimal>() {
+                    attrs.insert("quantity".to_string(), decimal_value(quantity));
+                }
+                let node = QdImportNode {
+                    r#type: QD_STR.to_string(),
+                    attrs,
+                    children: vec![],
+                };
+                push_node(&mut plan.nodes, stack.last().map(|(_, p)| p), node);
+                plan.qd_count += 1;
+            }
+            QdRowKind::Skip | QdRowKind::Unknown => {}
+        }
+        plan.rows.push(item);
+    }
+    plan
+}
+
+/// 按路径把节点加入树 返回新节点路径
+fn push_node(
+    nodes: &mut Vec<QdImportNode>,
+    parent: Option<&Vec<usize>>,
+    node: QdImportNode,
+) -> Vec<usize> {
+    let mut path = parent.cloned().unwrap_or_default();
+    let mut siblings = nodes;
+    for index in path.iter() {
+        siblings = &mut siblings[*index].children;
+    }
+    siblings.push(node);
+    path.push(siblings.len() - 1);
+    path
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use calamine::{open_workbook_auto, Reader};
+use serde::{Deserialize, Serialize};
+
+/// 表格数据 每行为单元格文本
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableData {
+    /// 工作簿中的全部工作表名称 CSV 为空
+    pub sheets: Vec<String>,
+    /// 实际读取的工作表
+    pub sheet: Option<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// 读取 XLSX/XLS/CSV 文件 未指定工作表时读取第一个工作表
+pub fn read_table(path: &Path, sheet: Option<&str>) -> anyhow::Result<TableData> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "csv" => {
+            let content = decode_csv(std::fs::read(path)?)?;
+            Ok(TableData {
+                sheets: vec![],
+                sheet: None,
+                rows: parse_csv(content.trim_start_matches('\u{feff}')),
+            })
+        }
+        "xlsx" | "xlsm" | "xls" => {
+            let mut workbook =
+                open_workbook_auto(path).map_err(|e| anyhow::anyhow!("打开文件失败: {}", e))?;
+            let sheets = workbook.sheet_names();
+            let name = match sheet {
+                Some(name) => name.to_string(),
+                None => sheets
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("文件中没有工作表".to_string()))?,
+            };
+            let range = workbook
+                .worksheet_range(&name)
+                .map_err(|e| anyhow::anyhow!("读取工作表 {} 失败: {}", name, e))?;
+            let rows = range
+                .rows()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| cell.to_string().trim().to_string())
+                        .collect()
+                })
+                .collect();
+            Ok(TableData {
+                sheets,
+                sheet: Some(name),
+                rows,
+            })
+        }
+        _ => Err(anyhow::anyhow!("不支持的文件类型: {}", extension)),
+    }
+}
+
+/// CSV 文本解码 非 UTF-8 时按 GBK 解码(Excel 中文系统默认另存的编码)
+fn decode_csv(bytes: Vec<u8>) -> anyhow::Result<String> {
+    match String::from_utf8(bytes) {
+        Ok(content) => Ok(content),
+        Err(e) => {
+            let (content, _, had_errors) = encoding_rs::GBK.decode(e.as_bytes());
+            if had_errors {
+                return Err(anyhow::anyhow!(
+                    "CSV 文件编码无法识别 请使用 UTF-8 或 GBK 编码".to_string()
+                ));
+            }
+            Ok(content.into_owned())
+        }
+    }
+}
+
+/// 解析 CSV 支持双引号包裹的字段及字段内换行
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if quoted {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' => quoted = true,
+            ',' => row.push(std::mem::take(&mut field).trim().to_string()),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field).trim().to_string());
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field.trim().to_string());
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_csv, parse_csv};
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(
+            parse_csv("编码,名称\r\n010101001,\"挖土方, 三类土\"\r\n"),
+            vec![
+                row(&["编码", "名称"]),
+                row(&["010101001", "挖土方, 三类土"])
+            ]
+        );
+    }
+
+    #[test]
+    fn doubled_quotes() {
+        assert_eq!(
+            parse_csv("\"C30 \"\"商品\"\"混凝土\",m3"),
+            vec![row(&["C30 \"商品\"混凝土", "m3"])]
+        );
+    }
+
+    #[test]
+    fn newline_inside_field() {
+        assert_eq!(
+            parse_csv("\"1.土壤类别\n2.挖土深度\",m3\n"),
+            vec![row(&["1.土壤类别\n2.挖土深度", "m3"])]
+        );
+    }
+
+    #[test]
+    fn empty_fields() {
+        assert_eq!(
+            parse_csv("a,,c\n,\n"),
+            vec![row(&["a", "", "c"]), row(&["", ""])]
+        );
+    }
+
+    #[test]
+    fn decode_gbk() {
+        let (bytes, _, _) = encoding_rs::GBK.encode("编码,名称");
+        assert_eq!(decode_csv(bytes.into_owned()).unwrap(), "编码,名称");
+        assert_eq!(decode_csv("编码".as_bytes().to_vec()).unwrap(), "编码");
+    }
+}
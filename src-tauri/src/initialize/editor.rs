@@ -10,11 +10,17 @@ use crate::{
     core::{collab_editor::{CollabEditor, CollabEditorOptions}, demo_editor::{DemoEditor, DemoEditorOptions}},
     marks, middleware,
     nodes::{
+        column::CustomColumn,
+        djgc::{self, DJGC_STR},
         fbfx_csxm::{init_fbfx_csxm_fields, CSXM_STR, DE_STR, FBFX_STR},
         gcxm::{init_project_structure, DWGC_STR},
         rcj::{init_rcj_fields, RCJ_STR},
     },
-    plugins::inc::IncStateField,
+    plugins::{
+        inc::{IncStateField, INC_PLUGIN_KEY},
+        lock::LockPlugin,
+        validate::ValidatePlugin,
+    },
 };
 //获取编辑器
 pub async fn init_editor(options: DemoEditorOptions) -> DemoEditor {
@@ -33,16 +39,17 @@ pub async fn init_collab_editor(options: CollabEditorOptions) -> CollabEditor {
 }
 
 //获取编辑器配置
-pub async fn init_collab_options(create_callback: Arc<dyn NodePoolFnTrait>,room_name:String) -> CollabEditorOptions {
+pub async fn init_collab_options(create_callback: Arc<dyn NodePoolFnTrait>,room_name:String,columns: &[CustomColumn]) -> CollabEditorOptions {
     let mut builder = EditorOptionsBuilder::new();
     builder = builder
         .content(Content::NodePoolFn(create_callback))
         // 设置历史记录限制
         .history_limit(20)
         // 添加扩展
-        .extensions(init_extension())
+        .extensions(init_extension(columns))
         // 添加中间件
-        .add_middleware(middleware::collect_fbfx_csxm::CollectFbfxCsxmMiddleware);
+        .add_middleware(middleware::collect_fbfx_csxm::CollectFbfxCsxmMiddleware)
+        .add_middleware(middleware::xmbm::XmbmMiddleware);
     let options = builder.build();
     CollabEditorOptions {
         editor_options: options,
@@ -52,23 +59,24 @@ pub async fn init_collab_options(create_callback: Arc<dyn NodePoolFnTrait>,room_
 }
 
 //获取编辑器配置
-pub async fn init_options(create_callback: Arc<dyn NodePoolFnTrait>) -> DemoEditorOptions {
+pub async fn init_options(create_callback: Arc<dyn NodePoolFnTrait>,columns: &[CustomColumn]) -> DemoEditorOptions {
     let mut builder = EditorOptionsBuilder::new();
     builder = builder
         .content(Content::NodePoolFn(create_callback))
         // 设置历史记录限制
         .history_limit(20)
         // 添加扩展
-        .extensions(init_extension())
+        .extensions(init_extension(columns))
         // 添加中间件
-        .add_middleware(middleware::collect_fbfx_csxm::CollectFbfxCsxmMiddleware);
+        .add_middleware(middleware::collect_fbfx_csxm::CollectFbfxCsxmMiddleware)
+        .add_middleware(middleware::xmbm::XmbmMiddleware);
     let options = builder.build();
     DemoEditorOptions {
         editor_options: options,
     }
 }
-//获取扩展
-pub fn init_extension() -> Vec<Extensions> {
+//获取扩展 columns 为工程项目的自定义列
+pub fn init_extension(columns: &[CustomColumn]) -> Vec<Extensions> {
     let mut extensions = vec![
         Extensions::M(marks::BG_COLOR.clone()),
         Extensions::M(marks::FOOTNOTE.clone()),
@@ -81,24 +89,42 @@ pub fn init_extension() -> Vec<Extensions> {
         }
         extensions.push(Extensions::N(node));
     }
-    let fbfx_csxm_nodes = init_fbfx_csxm_fields();
+    let fbfx_csxm_nodes = init_fbfx_csxm_fields(columns);
     for mut node in fbfx_csxm_nodes {
         if node.get_name() == DE_STR {
-            node.set_content(&format!("{}*", RCJ_STR));
+            node.set_content(&format!("{}* {}?", RCJ_STR, DJGC_STR));
         }
         extensions.push(Extensions::N(node));
     }
     // 定额下人材机明细Node
     let rcj_node = init_rcj_fields();
     extensions.push(Extensions::N(rcj_node));
+    // 定额下单价构成Node
+    for node in djgc::init_nodes() {
+        extensions.push(Extensions::N(node));
+    }
     let mut extension = Extension::new();
     let inc_plugin = Plugin::new(PluginSpec {
-        key: ("inc_plugin".to_string(), "增量数据插件".to_string()),
+        key: (INC_PLUGIN_KEY.to_string(), "增量数据插件".to_string()),
         state_field: Some(Arc::new(IncStateField)),
         tr: None,
         priority: 10,
     });
     extension.add_plugin(Arc::new(inc_plugin));
+    let lock_plugin = Plugin::new(PluginSpec {
+        key: ("lock_plugin".to_string(), "锁定综合单价插件".to_string()),
+        state_field: None,
+        tr: Some(Arc::new(LockPlugin)),
+        priority: 5,
+    });
+    extension.add_plugin(Arc::new(lock_plugin));
+    let validate_plugin = Plugin::new(PluginSpec {
+        key: ("validate_plugin".to_string(), "属性校验插件".to_string()),
+        state_field: None,
+        tr: Some(Arc::new(ValidatePlugin)),
+        priority: 5,
+    });
+    extension.add_plugin(Arc::new(validate_plugin));
     extensions.push(Extensions::E(extension));
     extensions
 }
@@ -7,5 +7,6 @@ pub mod editor;
 pub async fn init_contex() {
     let map_p: DashMap<String, Box<dyn EditorTrait>> = DashMap::new();
     ContextHelper::set(map_p);
+    crate::report::template::init_templates();
 }
         
\ No newline at end of file
@@ -12,6 +12,14 @@ pub mod plugins;
 pub mod exetensions;
 // 工具层
 pub mod utils;
+// 导出层
+pub mod export;
+// 导入层
+pub mod import;
+// 数据交换层
+pub mod exchange;
+// 报表层
+pub mod report;
 // 初始化层
 pub mod initialize;
 
@@ -77,4 +85,9 @@ impl ContextHelper {
         let map = ContextHelper::get::<DashMap<String, Box<dyn EditorTrait>>>();
         map.insert(name.to_string(), editor);
     }
+    /// 移除价格编辑器
+    pub fn remove_editor(name: &str) {
+        let map = ContextHelper::get::<DashMap<String, Box<dyn EditorTrait>>>();
+        map.remove(name);
+    }
 }
@@ -1,10 +1,22 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use app_lib::{initialize::init_contex, router::build_app, serve::AppBuilder};
+use app_lib::{
+    export::xlsx::export_xlsx,
+    initialize::init_contex,
+    router::build_app,
+    serve::AppBuilder,
+    utils::push::{
+        add_window_subscription, remove_window_subscription, subscribe, subscribed_windows,
+        window_pushes, IncSubscription,
+    },
+    ContextHelper,
+};
 use axum::{http::StatusCode, response::IntoResponse, Router};
 use mf_state::init_logging;
-use tauri::{tray::TrayIconBuilder, tray::TrayIconEvent, AppHandle, Listener, Manager};
+use tauri::{tray::TrayIconBuilder, tray::TrayIconEvent, AppHandle, Emitter, Listener, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::broadcast::error::RecvError;
 
 // 自定义事件处理函数
 fn handle_tauri_error(error: tauri::Error) {
@@ -136,6 +148,95 @@ fn handle_logout(_window: tauri::Window) -> Result<(), String> {
     Ok(())
 }
 
+// 导出 Excel 弹出保存对话框 返回保存路径 取消时返回 None
+#[tauri::command]
+async fn export_excel(
+    app: AppHandle,
+    editor_name: String,
+    id: String,
+) -> Result<Option<String>, String> {
+    let (file_name, content) = {
+        let editor = ContextHelper::get_editor(&editor_name).ok_or("工程项目不存在")?;
+        let doc = editor.doc().await;
+        export_xlsx(&doc, &id).map_err(|e| format!("导出失败: {}", e))?
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(&file_name)
+        .add_filter("Excel", &["xlsx"])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let path = match rx.await.map_err(|e| format!("保存对话框异常: {}", e))? {
+        Some(path) => path
+            .into_path()
+            .map_err(|e| format!("保存路径无效: {}", e))?,
+        None => return Ok(None),
+    };
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| format!("保存文件失败: {}", e))?;
+    Ok(Some(path.display().to_string()))
+}
+
+// 订阅增量数据 root_id 为空时订阅整个工程项目 变更通过 inc_data 事件推送到当前窗口
+#[tauri::command]
+fn subscribe_inc(
+    window: tauri::Window,
+    editor_name: String,
+    root_id: Option<String>,
+) -> Result<(), String> {
+    if ContextHelper::get_editor(&editor_name).is_none() {
+        return Err("工程项目不存在".to_string());
+    }
+    add_window_subscription(
+        window.label(),
+        IncSubscription {
+            editor_name,
+            root_id,
+        },
+    );
+    Ok(())
+}
+
+// 取消订阅增量数据 editor_name 为空时取消当前窗口全部订阅
+#[tauri::command]
+fn unsubscribe_inc(
+    window: tauri::Window,
+    editor_name: Option<String>,
+    root_id: Option<String>,
+) -> Result<(), String> {
+    let subscription = editor_name.map(|editor_name| IncSubscription {
+        editor_name,
+        root_id,
+    });
+    remove_window_subscription(window.label(), subscription.as_ref());
+    Ok(())
+}
+
+// 转发增量数据到已订阅的窗口 丢失事件时通知窗口按版本重新获取
+async fn forward_inc_data(app: AppHandle) {
+    let mut receiver = subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                for (label, push) in window_pushes(&event) {
+                    if let Err(e) = app.emit_to(label.as_str(), "inc_data", push) {
+                        tracing::warn!("推送增量数据失败: {}", e);
+                    }
+                }
+            }
+            Err(RecvError::Lagged(_)) => {
+                for label in subscribed_windows() {
+                    let _ = app.emit_to(label.as_str(), "inc_resync", ());
+                }
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // 初始化日志系统，降低tao警告级别
@@ -159,6 +260,9 @@ async fn main() -> anyhow::Result<()> {
         .setup(|app| {
             let app_handle = app.handle().clone();
 
+            // 推送增量数据
+            tokio::spawn(forward_inc_data(app_handle.clone()));
+
             // 设置启动屏幕的自动关闭机制
             let app_handle_timeout = app_handle.clone();
             tokio::spawn(async move {
@@ -185,6 +289,12 @@ async fn main() -> anyhow::Result<()> {
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // 窗口关闭后移除其增量数据订阅
+            if let tauri::WindowEvent::Destroyed = event {
+                remove_window_subscription(window.label(), None);
+            }
+        })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
@@ -193,7 +303,10 @@ async fn main() -> anyhow::Result<()> {
             show_main_window,
             quit_app,
             show_tray_menu,
-            hide_tray_menu
+            hide_tray_menu,
+            export_excel,
+            subscribe_inc,
+            unsubscribe_inc
         ])
         .run(tauri::generate_context!())
         .map_err(|e| {
@@ -1,8 +1,26 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use mf_core::{middleware::Middleware, ForgeResult};
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
 use mf_state::{State, Transaction};
+use serde_json::Value;
+
+use crate::{
+    middleware::touched_ids,
+    nodes::{
+        fbfx_csxm::{CSXM_STR, DE_STR, FBFX_STR, FB_STR, QD_STR},
+        gcxm::DWGC_STR,
+    },
+    utils::{
+        money::{decimal_value, get_decimal, Decimal},
+        node::{children, get_str},
+        price::{de_zgf_price, is_locked, locked_ancestor, PriceOptions},
+    },
+};
+
+/// 汇总事务标记 避免汇总结果再次触发汇总
+pub const ROLLUP_META: &str = "collect_fbfx_csxm";
 
 /// 收集 分部分项 措施项目 汇总 中间件
 /// 当 编辑区 分部分项 措施项目节点 更新后需要 收集 分部分项 措施项目 汇总
@@ -20,15 +38,179 @@ impl Middleware for CollectFbfxCsxmMiddleware {
     /// 返回一个可能包含需要额外处理的事务的 MiddlewareResult
     async fn after_dispatch(
         &self,
-        _state: Option<Arc<State>>,
+        state: Option<Arc<State>>,
         transactions: &[Transaction],
     ) -> ForgeResult<Option<Transaction>> {
-        println!("分部分项 措施项目 汇总");
+        let state = match state {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let mut ids: Vec<NodeId> = Vec::new();
         for tr in transactions {
-            if let Some(_) = tr.get_meta::<Vec<String>>("de_ids") {
-                //汇总对应的定额 价格 向上汇总
+            if tr.get_meta::<bool>(ROLLUP_META).is_some() {
+                continue;
+            }
+            ids.extend(rollup_ids(tr));
+        }
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        //汇总对应的定额 价格 向上汇总
+        let doc = state.doc();
+        let changes = Rollup::new(&doc).run(&ids);
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        let mut tr = state.tr();
+        for (id, attrs) in changes {
+            tr.set_node_attribute(id, attrs.into())?;
+        }
+        tr.set_meta(ROLLUP_META, true);
+        Ok(Some(tr))
+    }
+}
+
+/// 事务中被修改的节点 另加事务 meta 中标记的定额
+fn rollup_ids(tr: &Transaction) -> Vec<NodeId> {
+    let mut ids = touched_ids(tr);
+    if let Some(de_ids) = tr.get_meta::<Vec<String>>("de_ids") {
+        ids.extend(de_ids.iter().cloned());
+    }
+    ids
+}
+
+/// 自底向上的价格汇总
+/// 定额 → 清单 → 分部 → 分部分项/措施项目 → 单位工程
+/// 单价、合价按所属单位工程的取整规则计算 以字符串回填精确值
+struct Rollup<'a> {
+    doc: &'a NodePool,
+    /// 本次汇总中已计算的值 父节点汇总时优先读取
+    values: HashMap<NodeId, HashMap<String, Decimal>>,
+}
+
+impl<'a> Rollup<'a> {
+    fn new(doc: &'a NodePool) -> Self {
+        Self {
+            doc,
+            values: HashMap::new(),
+        }
+    }
+
+    /// 返回需要回填的节点属性
+    fn run(mut self, ids: &[NodeId]) -> Vec<(NodeId, HashMap<String, Value>)> {
+        let mut changes = Vec::new();
+        for node in self.affected_nodes(ids) {
+            let computed = self.compute(&node);
+            let mut attrs = HashMap::new();
+            for (key, value) in computed.iter() {
+                let value = decimal_value(*value);
+                if node.attrs.get_value::<Value>(key).as_ref() != Some(&value) {
+                    attrs.insert(key.clone(), value);
+                }
+            }
+            self.values.insert(node.id.clone(), computed);
+            if !attrs.is_empty() {
+                changes.push((node.id.clone(), attrs));
+            }
+        }
+        changes
+    }
+
+    /// 受影响的节点及其到单位工程的所有祖先 按深度倒序(子节点先于父节点)
+    fn affected_nodes(&self, ids: &[NodeId]) -> Vec<Arc<Node>> {
+        let mut depth: HashMap<NodeId, (usize, Arc<Node>)> = HashMap::new();
+        for id in ids {
+            let mut chain = Vec::new();
+            let mut current = self.doc.get_node(id);
+            while let Some(node) = current {
+                let is_dwgc = node.r#type == DWGC_STR;
+                current = if is_dwgc {
+                    None
+                } else {
+                    self.doc.get_parent_node(&node.id)
+                };
+                chain.push(node);
+                if is_dwgc {
+                    break;
+                }
+            }
+            let len = chain.len();
+            for (index, node) in chain.into_iter().enumerate() {
+                depth.entry(node.id.clone()).or_insert((len - index, node));
+            }
+        }
+        let mut nodes: Vec<(usize, Arc<Node>)> = depth.into_values().collect();
+        nodes.sort_by(|a, b| b.0.cmp(&a.0));
+        nodes.into_iter().map(|(_, node)| node).collect()
+    }
+
+    fn value(&self, node: &Node, key: &str) -> Decimal {
+        self.values
+            .get(&node.id)
+            .and_then(|values| values.get(key).copied())
+            .unwrap_or_else(|| get_decimal(node, key))
+    }
+
+    fn sum_children(&self, node: &Node, types: &[&str], key: &str) -> Decimal {
+        children(self.doc, &node.id)
+            .iter()
+            .filter(|child| types.contains(&child.r#type.as_str()))
+            .map(|child| self.value(child, key))
+            .sum()
+    }
+
+    fn compute(&self, node: &Node) -> HashMap<String, Decimal> {
+        let mut values = HashMap::new();
+        //锁定行的组成不参与汇总 锁定行按锁定的单价 × 工程量 计入上级
+        if locked_ancestor(self.doc, &node.id).is_some() {
+            return values;
+        }
+        let rounding = PriceOptions::of(self.doc, &node.id).rounding;
+        let unit = get_str(node, "unit");
+        let quantity = get_decimal(node, "quantity");
+        if is_locked(node) {
+            values.insert(
+                "zgfTotal".to_string(),
+                rounding.amount(get_decimal(node, "zgfPrice"), quantity, &unit),
+            );
+            return values;
+        }
+        match node.r#type.as_str() {
+            DE_STR => {
+                let zgf_price = de_zgf_price(self.doc, &node.id);
+                values.insert("zgfPrice".to_string(), rounding.price(zgf_price));
+                values.insert(
+                    "zgfTotal".to_string(),
+                    rounding.amount(zgf_price, quantity, &unit),
+                );
+            }
+            QD_STR => {
+                let zgf_total = self.sum_children(node, &[DE_STR], "zgfTotal");
+                let quantity = rounding.quantity(&unit, quantity);
+                values.insert("zgfTotal".to_string(), zgf_total);
+                values.insert(
+                    "zgfPrice".to_string(),
+                    if quantity.is_zero() {
+                        rounding.price(Decimal::ZERO)
+                    } else {
+                        rounding.price(zgf_total / quantity)
+                    },
+                );
+            }
+            FB_STR | FBFX_STR | CSXM_STR => {
+                values.insert(
+                    "zgfTotal".to_string(),
+                    self.sum_children(node, &[FB_STR, QD_STR], "zgfTotal"),
+                );
+            }
+            DWGC_STR => {
+                values.insert(
+                    "zgfTotal".to_string(),
+                    self.sum_children(node, &[FBFX_STR, CSXM_STR], "zgfTotal"),
+                );
             }
+            _ => {}
         }
-        Ok(None)
+        values
     }
 }
@@ -1 +1,30 @@
+use mf_model::types::NodeId;
+use mf_state::Transaction;
+use mf_transform::{
+    attr_step::AttrStep,
+    node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
+};
+
 pub mod collect_fbfx_csxm;
+pub mod xmbm;
+
+/// 事务中被修改的节点 新增、删除、移动节点时取其父节点
+pub fn touched_ids(tr: &Transaction) -> Vec<NodeId> {
+    let mut ids = Vec::new();
+    for step in tr.steps.iter() {
+        if let Some(attr_step) = step.downcast_ref::<AttrStep>() {
+            ids.push(attr_step.id.clone());
+        }
+        if let Some(add_step) = step.downcast_ref::<AddNodeStep>() {
+            ids.push(add_step.parent_id.clone());
+        }
+        if let Some(remove_step) = step.downcast_ref::<RemoveNodeStep>() {
+            ids.push(remove_step.parent_id.clone());
+        }
+        if let Some(move_step) = step.downcast_ref::<MoveNodeStep>() {
+            ids.push(move_step.source_parent_id.clone());
+            ids.push(move_step.target_parent_id.clone());
+        }
+    }
+    ids
+}
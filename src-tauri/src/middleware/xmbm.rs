@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mf_core::{middleware::Middleware, ForgeResult};
+use mf_model::types::NodeId;
+use mf_state::{State, Transaction};
+
+use crate::{
+    commands::xmbm::{apply_codes, XMBM_META},
+    middleware::{collect_fbfx_csxm::ROLLUP_META, touched_ids},
+    utils::xmbm::{dwgc_of, renumber, CodeMode},
+};
+
+/// 项目编码 中间件
+/// 分部、清单 新增、移动、删除或修改后 按工程项目的编码方式 补全或重排所在单位工程的项目编码
+#[derive(Debug)]
+pub struct XmbmMiddleware;
+
+#[async_trait]
+impl Middleware for XmbmMiddleware {
+    fn name(&self) -> String {
+        "xmbm".to_string()
+    }
+
+    async fn after_dispatch(
+        &self,
+        state: Option<Arc<State>>,
+        transactions: &[Transaction],
+    ) -> ForgeResult<Option<Transaction>> {
+        let state = match state {
+            Some(state) => state,
+            None => return Ok(None),
+        };
+        let doc = state.doc();
+        let mut dwgc_ids: Vec<NodeId> = Vec::new();
+        for tr in transactions {
+            if tr.get_meta::<bool>(XMBM_META).is_some()
+                || tr.get_meta::<bool>(ROLLUP_META).is_some()
+            {
+                continue;
+            }
+            for id in touched_ids(tr) {
+                if let Some(dwgc_id) = dwgc_of(&doc, &id) {
+                    if !dwgc_ids.contains(&dwgc_id) {
+                        dwgc_ids.push(dwgc_id);
+                    }
+                }
+            }
+        }
+        let mut codes = Vec::new();
+        for dwgc_id in dwgc_ids.iter() {
+            match renumber(&doc, dwgc_id, CodeMode::of(&doc, dwgc_id)) {
+                Ok(changes) => codes.extend(changes),
+                Err(e) => tracing::warn!("项目编码失败: {}", e),
+            }
+        }
+        if codes.is_empty() {
+            return Ok(None);
+        }
+        let mut tr = state.tr();
+        apply_codes(&mut tr, codes)?;
+        tr.set_meta(XMBM_META, true);
+        Ok(Some(tr))
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+
+use mf_model::{node::Node, node_pool::NodePool, schema::AttributeSpec};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    nodes::{
+        fbfx_csxm::{get_attr_spec, DE_STR, FB_STR, QD_STR},
+        field::{FieldSpec, FieldType},
+        gcxm::CUSTOM_COLUMNS_ATTR,
+    },
+    utils::node::{get_bool, get_str},
+};
+
+/// 自定义列适用的节点类型
+pub const CUSTOM_COLUMN_TYPES: [&str; 3] = [FB_STR, QD_STR, DE_STR];
+
+/// 工程项目自定义列 如 备注、专业、施工部位、清单类别
+/// 定义保存在工程项目的 customColumns 属性中 创建编辑器时合并到 分部、清单、定额 的属性定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomColumn {
+    /// 属性名
+    pub key: String,
+    /// 列标题
+    pub label: String,
+    pub r#type: FieldType,
+    #[serde(default)]
+    pub default: Option<Value>,
+}
+
+impl CustomColumn {
+    pub fn spec(&self) -> FieldSpec {
+        FieldSpec::of(self.r#type.clone())
+    }
+
+    /// 属性定义 未设置默认值时 布尔列为 false 其余为空
+    pub fn attribute_spec(&self) -> AttributeSpec {
+        let default = match (&self.default, &self.r#type) {
+            (Some(value), _) => value.clone(),
+            (None, FieldType::Bool) => Value::Bool(false),
+            (None, _) => Value::String(String::new()),
+        };
+        AttributeSpec {
+            default: Some(default),
+        }
+    }
+
+    /// 报表中显示的文本 布尔列显示 是/否
+    pub fn text(&self, node: &Node) -> String {
+        match self.r#type {
+            FieldType::Bool => if get_bool(node, &self.key) {
+                "是"
+            } else {
+                "否"
+            }
+            .to_string(),
+            _ => get_str(node, &self.key),
+        }
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// 校验自定义列定义 返回默认值转换为存储格式后的定义
+pub fn check_columns(columns: &[CustomColumn]) -> Result<Vec<CustomColumn>, String> {
+    let builtin = get_attr_spec();
+    let mut keys = HashSet::new();
+    let mut result = Vec::new();
+    for column in columns.iter() {
+        let key = column.key.trim();
+        if !is_valid_key(key) {
+            return Err(format!(
+                "自定义列 {} 的属性名只能包含字母、数字、下划线 且不能以数字开头",
+                column.key
+            ));
+        }
+        if builtin.contains_key(key) {
+            return Err(format!("自定义列 {} 与内置属性重名", key));
+        }
+        if !keys.insert(key.to_string()) {
+            return Err(format!("自定义列 {} 重复", key));
+        }
+        if let FieldType::Enum { values } = &column.r#type {
+            if values.is_empty() {
+                return Err(format!("自定义列 {} 缺少可选值", key));
+            }
+        }
+        let default = match &column.default {
+            Some(value) => Some(
+                column
+                    .spec()
+                    .coerce(value)
+                    .map_err(|e| format!("自定义列 {} 的默认值{}", key, e))?,
+            ),
+            None => None,
+        };
+        result.push(CustomColumn {
+            key: key.to_string(),
+            label: if column.label.trim().is_empty() {
+                key.to_string()
+            } else {
+                column.label.trim().to_string()
+            },
+            r#type: column.r#type.clone(),
+            default,
+        });
+    }
+    Ok(result)
+}
+
+/// 读取属性值中的自定义列定义 格式错误时为空
+pub fn parse_columns(value: Option<&Value>) -> Vec<CustomColumn> {
+    value
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// 工程项目的自定义列
+pub fn custom_columns(doc: &NodePool) -> Vec<CustomColumn> {
+    match doc.get_node(doc.root_id()) {
+        Some(root) => parse_columns(root.attrs.get_value::<Value>(CUSTOM_COLUMNS_ATTR).as_ref()),
+        None => vec![],
+    }
+}
+
+/// 自定义列的属性定义 属性名 → 属性定义
+pub fn column_specs(columns: &[CustomColumn]) -> HashMap<String, FieldSpec> {
+    columns
+        .iter()
+        .map(|column| (column.key.clone(), column.spec()))
+        .collect()
+}
+
+/// 节点类型的自定义列属性定义 不适用自定义列的节点类型为空
+pub fn custom_field_specs(doc: &NodePool, node_type: &str) -> HashMap<String, FieldSpec> {
+    if !CUSTOM_COLUMN_TYPES.contains(&node_type) {
+        return HashMap::new();
+    }
+    column_specs(&custom_columns(doc))
+}
@@ -1,8 +1,20 @@
+use std::collections::HashMap;
+
 use mf_core::node::Node;
 use mf_macro::node;
+
+use crate::nodes::field::FieldSpec;
+
+pub const DJGC_STR: &str = "djgc";
+pub const DJGC_ROW_STR: &str = "djgcRowNode";
+
+/// 单价构成行 type 属性
+pub const DJGC_TYPE_GLF: &str = "管理费";
+pub const DJGC_TYPE_LR: &str = "利润";
+
 lazy_static! {
-    pub static ref DJGC: Node = node!("djgc", "单价构成","","value"=>"".into());
-    pub static ref DJGC_NODE: Node = node!("djgcRowNode", "单价构成行节点","","qfCode"=>"".into(),"type"=>"".into(),"code"=>"".into(),"caculateBase"=>"".into(),"desc"=>"".into(),"rate"=>"".into(),"price"=>0.into());
+    pub static ref DJGC: Node = node!(DJGC_STR, "单价构成","","value"=>"".into());
+    pub static ref DJGC_NODE: Node = node!(DJGC_ROW_STR, "单价构成行节点","","qfCode"=>"".into(),"type"=>"".into(),"code"=>"".into(),"caculateBase"=>"".into(),"desc"=>"".into(),"rate"=>"".into(),"price"=>0.into());
 }
 
 ///构建单价构成节点 节点定义
@@ -22,7 +34,20 @@ lazy_static! {
 pub fn init_nodes() -> Vec<Node> {
     let mut nodes = vec![DJGC_NODE.clone()];
     let mut djgc = DJGC.clone();
-    djgc.set_content("djgcRowNode+");
+    djgc.set_content(&format!("{}+", DJGC_ROW_STR));
     nodes.push(djgc);
     nodes
 }
+
+/// 单价构成行 属性类型定义
+pub fn init_field_specs() -> HashMap<String, HashMap<String, FieldSpec>> {
+    HashMap::from([(
+        DJGC_ROW_STR.to_string(),
+        HashMap::from([
+            ("code".to_string(), FieldSpec::string()),
+            ("caculateBase".to_string(), FieldSpec::string()),
+            ("rate".to_string(), FieldSpec::decimal(4)),
+            ("price".to_string(), FieldSpec::decimal(2)),
+        ]),
+    )])
+}
@@ -4,6 +4,8 @@ use mf_core::node::Node;
 use mf_macro::node;
 use mf_model::schema::AttributeSpec;
 
+use crate::nodes::{column::CustomColumn, field::FieldSpec};
+
 pub const FB_STR: &str = "fb";
 pub const QD_STR: &str = "qd";
 pub const DE_STR: &str = "de";
@@ -19,13 +21,18 @@ lazy_static! {
     pub static ref FBFX: Node = node!(FBFX_STR, "分部分项", &format!("({}|{})+", FB_STR, QD_STR));
     pub static ref CSXM: Node = node!(CSXM_STR, "措施项目", &format!("({}|{})+", FB_STR, QD_STR));
 }
-pub fn init_fbfx_csxm_fields() -> Vec<Node> {
+/// columns 为工程项目的自定义列 合并到 分部、清单、定额 的属性定义
+pub fn init_fbfx_csxm_fields(columns: &[CustomColumn]) -> Vec<Node> {
+    let mut row_attrs = get_attr_spec();
+    for column in columns.iter() {
+        row_attrs.insert(column.key.clone(), column.attribute_spec());
+    }
     let mut fb = FB.clone();
-    fb.set_attrs(get_attr_spec());
+    fb.set_attrs(row_attrs.clone());
     let mut qd = QD.clone();
-    qd.set_attrs(get_attr_spec());
+    qd.set_attrs(row_attrs.clone());
     let mut de = DE.clone();
-    de.set_attrs(get_attr_spec());
+    de.set_attrs(row_attrs);
     let mut rcj = RCJ.clone();
     rcj.set_attrs(get_attr_spec());
     let mut fbfx = FBFX.clone();
@@ -42,10 +49,16 @@ fn get_attr_name(name: &str) -> HashMap<String, AttributeSpec> {
             default: Some(name.into()),
         },
     );
+    att.insert(
+        "zgfTotal".to_string(),
+        AttributeSpec {
+            default: Some(0.into()),
+        },
+    ); //暂估合价 默认0
     att
 }
 
-fn get_attr_spec() -> HashMap<String, AttributeSpec> {
+pub fn get_attr_spec() -> HashMap<String, AttributeSpec> {
     let mut att = HashMap::new();
     att.insert(
         "projectCode".to_string(),
@@ -125,5 +138,53 @@ fn get_attr_spec() -> HashMap<String, AttributeSpec> {
             default: Some(0.into()),
         },
     ); //直接费合价 默认0
+    att.insert(
+        "lockPrice".to_string(),
+        AttributeSpec {
+            default: Some(false.into()),
+        },
+    ); //锁定综合单价 默认不锁定
+    att.insert(
+        "lockedCost".to_string(),
+        AttributeSpec {
+            default: Some(serde_json::Value::Null),
+        },
+    ); //锁定时的单价构成快照 默认空
     att
 }
+
+/// 分部、清单、定额 属性类型定义
+fn get_field_specs() -> HashMap<String, FieldSpec> {
+    let price = || FieldSpec::decimal(2);
+    HashMap::from([
+        ("projectCode".to_string(), FieldSpec::string()),
+        ("projectName".to_string(), FieldSpec::string()),
+        ("unit".to_string(), FieldSpec::string()),
+        ("projectAttr".to_string(), FieldSpec::string()),
+        ("quantity".to_string(), FieldSpec::decimal(3)),
+        ("quantityExpression".to_string(), FieldSpec::string()),
+        ("sbfPrice".to_string(), price()),
+        ("sbfTotal".to_string(), price()),
+        ("zgfPrice".to_string(), price()),
+        ("zgfTotal".to_string(), price()),
+        ("zjfPrice".to_string(), price()),
+        ("zjfTotal".to_string(), price()),
+        ("lockPrice".to_string(), FieldSpec::bool()),
+    ])
+}
+
+/// 分部分项 措施项目 属性类型定义
+pub fn init_field_specs() -> HashMap<String, HashMap<String, FieldSpec>> {
+    let mut specs: HashMap<String, HashMap<String, FieldSpec>> =
+        [FB_STR, QD_STR, DE_STR, DE_RCJ_STR]
+            .iter()
+            .map(|name| (name.to_string(), get_field_specs()))
+            .collect();
+    for name in [FBFX_STR, CSXM_STR] {
+        specs.insert(
+            name.to_string(),
+            HashMap::from([("name".to_string(), FieldSpec::string().required())]),
+        );
+    }
+    specs
+}
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, fmt};
+
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use crate::{
+    nodes::{column::custom_field_specs, djgc, fbfx_csxm, gcxm, rcj},
+    utils::money::{decimal_value, parse_decimal, round, to_f64},
+};
+
+/// 属性类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum FieldType {
+    /// 小数 precision 为保留的小数位数
+    Decimal {
+        precision: u32,
+    },
+    Integer,
+    Enum {
+        values: Vec<String>,
+    },
+    String,
+    Bool,
+}
+
+impl FieldType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldType::Decimal { .. } => "decimal",
+            FieldType::Integer => "integer",
+            FieldType::Enum { .. } => "enum",
+            FieldType::String => "string",
+            FieldType::Bool => "bool",
+        }
+    }
+}
+
+/// 属性定义 类型、是否必填、取值范围
+/// 非必填属性允许空字符串或 null 表示未填写
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSpec {
+    pub r#type: FieldType,
+    pub required: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl FieldSpec {
+    pub fn of(r#type: FieldType) -> Self {
+        Self {
+            r#type,
+            required: false,
+            min: None,
+            max: None,
+        }
+    }
+    pub fn decimal(precision: u32) -> Self {
+        Self::of(FieldType::Decimal { precision })
+    }
+    pub fn integer() -> Self {
+        Self::of(FieldType::Integer)
+    }
+    pub fn enumeration(values: &[&str]) -> Self {
+        Self::of(FieldType::Enum {
+            values: values.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+    pub fn string() -> Self {
+        Self::of(FieldType::String)
+    }
+    pub fn bool() -> Self {
+        Self::of(FieldType::Bool)
+    }
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+    pub fn range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// 校验并转换为统一的存储格式 小数以字符串保存精确值并补足小数位数 如 12.505 → "12.51" 12.5 → "12.50"
+    pub fn coerce(&self, value: &Value) -> Result<Value, String> {
+        let empty = match value {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            _ => false,
+        };
+        if empty {
+            return if self.required {
+                Err("必填".to_string())
+            } else {
+                Ok(value.clone())
+            };
+        }
+        let coerced = match &self.r#type {
+            FieldType::Decimal { precision } => {
+                let number = parse_decimal(value).ok_or("不是有效的数字".to_string())?;
+                let number = round(number, *precision);
+                self.check_range(to_f64(number))?;
+                decimal_value(number)
+            }
+            FieldType::Integer => {
+                let number = to_number(value).ok_or("不是有效的整数".to_string())?;
+                if number.fract() != 0.0 {
+                    return Err("不是有效的整数".to_string());
+                }
+                Value::Number(Number::from(number as i64))
+            }
+            FieldType::Enum { values } => {
+                let text = to_text(value);
+                if !values.contains(&text) {
+                    return Err(format!("取值应为 {} 之一", values.join("/")));
+                }
+                Value::String(text)
+            }
+            FieldType::String => Value::String(to_text(value)),
+            FieldType::Bool => match value {
+                Value::Bool(b) => Value::Bool(*b),
+                Value::Number(n) if n.as_f64() == Some(0.0) => Value::Bool(false),
+                Value::Number(n) if n.as_f64() == Some(1.0) => Value::Bool(true),
+                Value::String(s) if s == "1" || s.eq_ignore_ascii_case("true") => Value::Bool(true),
+                Value::String(s) if s == "0" || s.eq_ignore_ascii_case("false") => {
+                    Value::Bool(false)
+                }
+                _ => return Err("不是有效的布尔值".to_string()),
+            },
+        };
+        if let Some(number) = coerced.as_f64() {
+            self.check_range(number)?;
+        }
+        Ok(coerced)
+    }
+
+    fn check_range(&self, number: f64) -> Result<(), String> {
+        if let Some(min) = self.min {
+            if number < min {
+                return Err(format!("不能小于 {}", min));
+            }
+        }
+        if let Some(max) = self.max {
+            if number > max {
+                return Err(format!("不能大于 {}", max));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// 属性校验错误
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub id: NodeId,
+    pub key: String,
+    pub message: String,
+}
+
+/// 属性校验错误列表 接口返回 422 及逐个字段的错误
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldErrors(pub Vec<FieldError>);
+
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self
+            .0
+            .iter()
+            .map(|error| format!("{}: {}", error.key, error.message))
+            .collect();
+        write!(f, "属性校验失败 {}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for FieldErrors {}
+
+lazy_static! {
+    /// 节点类型 → 属性名 → 属性定义
+    static ref FIELD_SPECS: HashMap<String, HashMap<String, FieldSpec>> = {
+        let mut specs = HashMap::new();
+        specs.extend(gcxm::init_field_specs());
+        specs.extend(fbfx_csxm::init_field_specs());
+        specs.extend(rcj::init_field_specs());
+        specs.extend(djgc::init_field_specs());
+        specs
+    };
+}
+
+pub fn field_spec(node_type: &str, key: &str) -> Option<&'static FieldSpec> {
+    FIELD_SPECS.get(node_type).and_then(|specs| specs.get(key))
+}
+
+/// 校验并转换节点属性 没有定义类型的属性原样保留
+/// 内置属性之外按工程项目的自定义列定义校验
+pub fn coerce_attrs(
+    doc: &NodePool,
+    id: &NodeId,
+    node_type: &str,
+    attrs: &mut HashMap<String, Value>,
+) -> Result<(), FieldErrors> {
+    let custom = custom_field_specs(doc, node_type);
+    let mut errors = Vec::new();
+    for (key, value) in attrs.iter_mut() {
+        if let Some(spec) = field_spec(node_type, key).or_else(|| custom.get(key)) {
+            match spec.coerce(value) {
+                Ok(coerced) => *value = coerced,
+                Err(message) => errors.push(FieldError {
+                    id: id.clone(),
+                    key: key.clone(),
+                    message,
+                }),
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(FieldErrors(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::FieldSpec;
+
+    #[test]
+    fn coerce_decimal_pads_precision() {
+        let spec = FieldSpec::decimal(2);
+        assert_eq!(spec.coerce(&json!("12.5")), Ok(json!("12.50")));
+        assert_eq!(spec.coerce(&json!(12)), Ok(json!("12.00")));
+        assert_eq!(spec.coerce(&json!("12.505")), Ok(json!("12.51")));
+        assert_eq!(spec.coerce(&json!(-12.505)), Ok(json!("-12.51")));
+        assert!(spec.coerce(&json!("abc")).is_err());
+    }
+
+    #[test]
+    fn coerce_empty_and_required() {
+        assert_eq!(FieldSpec::decimal(2).coerce(&json!("")), Ok(json!("")));
+        assert_eq!(FieldSpec::string().coerce(&Value::Null), Ok(Value::Null));
+        assert!(FieldSpec::string().required().coerce(&json!(" ")).is_err());
+    }
+
+    #[test]
+    fn coerce_range() {
+        let spec = FieldSpec::decimal(4).range(Some(0.0), Some(100.0));
+        assert_eq!(spec.coerce(&json!("100")), Ok(json!("100.0000")));
+        assert!(spec.coerce(&json!("100.001")).is_err());
+        assert!(spec.coerce(&json!(-1)).is_err());
+    }
+
+    #[test]
+    fn coerce_integer_enum_bool() {
+        assert_eq!(FieldSpec::integer().coerce(&json!("3")), Ok(json!(3)));
+        assert!(FieldSpec::integer().coerce(&json!("3.5")).is_err());
+        let spec = FieldSpec::enumeration(&["round", "multiply"]);
+        assert_eq!(spec.coerce(&json!("multiply")), Ok(json!("multiply")));
+        assert!(spec.coerce(&json!("floor")).is_err());
+        assert_eq!(FieldSpec::bool().coerce(&json!("1")), Ok(json!(true)));
+        assert_eq!(FieldSpec::bool().coerce(&json!(0)), Ok(json!(false)));
+        assert!(FieldSpec::bool().coerce(&json!("yes")).is_err());
+    }
+}
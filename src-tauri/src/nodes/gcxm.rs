@@ -4,10 +4,44 @@ use mf_core::node::Node;
 use mf_macro::node;
 use mf_model::schema::AttributeSpec;
 
+use crate::nodes::field::FieldSpec;
+
 pub const DWGC_STR: &str = "DWGC";
 pub const DXGC_STR: &str = "DXGC";
 pub const GCXM_STR: &str = "GCXM";
 
+/// 甲供材料处理方式 单位工程 jgclMode 属性
+/// include: 计入综合单价与工程造价
+/// exclude: 不计入综合单价
+/// deduct: 计入综合单价 在费用汇总中扣除
+pub const JGCL_MODE_INCLUDE: &str = "include";
+pub const JGCL_MODE_EXCLUDE: &str = "exclude";
+pub const JGCL_MODE_DEDUCT: &str = "deduct";
+
+/// 价差处理方式 单位工程 jcMode 属性
+/// price: 按市场价计入综合单价
+/// separate: 综合单价按定额基价计算 价差在费用汇总中单列
+pub const JC_MODE_PRICE: &str = "price";
+pub const JC_MODE_SEPARATE: &str = "separate";
+
+/// 取整方式 单位工程 roundMode 属性
+/// round: 单价、工程量先按小数位数取整 再相乘得到合价
+/// multiply: 未取整的单价 × 工程量 再按合价小数位数取整
+pub const ROUND_MODE_ROUND: &str = "round";
+pub const ROUND_MODE_MULTIPLY: &str = "multiply";
+
+/// 项目编码方式 工程项目 codeMode 属性
+/// manual: 保留手工编码 仅补全缺少顺序码的清单编码及空的分部编码
+/// auto: 新增、移动、删除后按树顺序重排全部编码
+pub const CODE_MODE_MANUAL: &str = "manual";
+pub const CODE_MODE_AUTO: &str = "auto";
+
+/// 工程项目上保存的补充定额、补充人材机定义 随工程文件一起流转
+pub const BC_LIBRARY_ATTR: &str = "bcLibrary";
+
+/// 工程项目上保存的 分部、清单、定额 自定义列定义
+pub const CUSTOM_COLUMNS_ATTR: &str = "customColumns";
+
 lazy_static! {
     pub static ref GCXM: Node = node!(GCXM_STR, "工程项目", &format!("{}+", DXGC_STR));
     pub static ref DXGC: Node = node!(
@@ -29,7 +63,36 @@ pub fn init_project_structure() -> Vec<Node> {
     let mut gcxm: Node = GCXM.clone();
     gcxm.set_top_node();
     // 设置工程项目字段
-    gcxm.set_attrs(init_project_structure_field("工程项目"));
+    let mut gcxm_attrs = init_project_structure_field("工程项目");
+    // 总说明 打印报表时输出
+    gcxm_attrs.insert(
+        "description".to_string(),
+        AttributeSpec {
+            default: Some("".into()),
+        },
+    );
+    // 项目编码方式
+    gcxm_attrs.insert(
+        "codeMode".to_string(),
+        AttributeSpec {
+            default: Some(CODE_MODE_MANUAL.into()),
+        },
+    );
+    // 补充定额、补充人材机
+    gcxm_attrs.insert(
+        BC_LIBRARY_ATTR.to_string(),
+        AttributeSpec {
+            default: Some(serde_json::json!({ "de": [], "rcj": [] })),
+        },
+    );
+    // 自定义列 创建工程项目时确定
+    gcxm_attrs.insert(
+        CUSTOM_COLUMNS_ATTR.to_string(),
+        AttributeSpec {
+            default: Some(serde_json::json!([])),
+        },
+    );
+    gcxm.set_attrs(gcxm_attrs);
     // 设置单项工程字段
     let mut dxgc = DXGC.clone();
     dxgc.set_attrs(init_project_structure_field("单项工程"));
@@ -67,5 +130,133 @@ pub fn init_unit_structure_field() -> HashMap<String, AttributeSpec> {
         ),
         // 合计金额（元）
         ("total".to_string(), AttributeSpec { default: None }),
+        // 暂估合价
+        (
+            "zgfTotal".to_string(),
+            AttributeSpec {
+                default: Some(0.into()),
+            },
+        ),
+        // 甲供材料处理方式
+        (
+            "jgclMode".to_string(),
+            AttributeSpec {
+                default: Some(JGCL_MODE_INCLUDE.into()),
+            },
+        ),
+        // 价差处理方式
+        (
+            "jcMode".to_string(),
+            AttributeSpec {
+                default: Some(JC_MODE_PRICE.into()),
+            },
+        ),
+        // 其他项目费(元) 暂列金额、暂估价、计日工、总承包服务费合计
+        (
+            "qtxmTotal".to_string(),
+            AttributeSpec {
+                default: Some(0.into()),
+            },
+        ),
+        // 规费费率(%) 计算基数为 分部分项 + 措施项目 + 其他项目
+        (
+            "gfRate".to_string(),
+            AttributeSpec {
+                default: Some(0.into()),
+            },
+        ),
+        // 税金税率(%) 计算基数为 税前工程造价
+        (
+            "sjRate".to_string(),
+            AttributeSpec {
+                default: Some(0.into()),
+            },
+        ),
+        // 工程量小数位数
+        (
+            "qtyPrecision".to_string(),
+            AttributeSpec {
+                default: Some(3.into()),
+            },
+        ),
+        // 按计量单位设置的工程量小数位数 如 {"t": 3, "个": 0}
+        (
+            "unitQtyPrecision".to_string(),
+            AttributeSpec {
+                default: Some(serde_json::json!({})),
+            },
+        ),
+        // 单价小数位数
+        (
+            "pricePrecision".to_string(),
+            AttributeSpec {
+                default: Some(2.into()),
+            },
+        ),
+        // 合价小数位数
+        (
+            "totalPrecision".to_string(),
+            AttributeSpec {
+                default: Some(2.into()),
+            },
+        ),
+        // 取整方式
+        (
+            "roundMode".to_string(),
+            AttributeSpec {
+                default: Some(ROUND_MODE_ROUND.into()),
+            },
+        ),
+    ])
+}
+
+/// 工程项目 属性类型定义
+pub fn init_field_specs() -> HashMap<String, HashMap<String, FieldSpec>> {
+    let name = || ("name".to_string(), FieldSpec::string().required());
+    let rate = || FieldSpec::decimal(4).range(Some(0.0), Some(100.0));
+    let precision = || FieldSpec::integer().range(Some(0.0), Some(10.0));
+    HashMap::from([
+        (
+            GCXM_STR.to_string(),
+            HashMap::from([
+                name(),
+                (
+                    "codeMode".to_string(),
+                    FieldSpec::enumeration(&[CODE_MODE_MANUAL, CODE_MODE_AUTO]),
+                ),
+            ]),
+        ),
+        (DXGC_STR.to_string(), HashMap::from([name()])),
+        (
+            DWGC_STR.to_string(),
+            HashMap::from([
+                name(),
+                (
+                    "jgclMode".to_string(),
+                    FieldSpec::enumeration(&[
+                        JGCL_MODE_INCLUDE,
+                        JGCL_MODE_EXCLUDE,
+                        JGCL_MODE_DEDUCT,
+                    ]),
+                ),
+                (
+                    "jcMode".to_string(),
+                    FieldSpec::enumeration(&[JC_MODE_PRICE, JC_MODE_SEPARATE]),
+                ),
+                (
+                    "qtxmTotal".to_string(),
+                    FieldSpec::decimal(2).range(Some(0.0), None),
+                ),
+                ("gfRate".to_string(), rate()),
+                ("sjRate".to_string(), rate()),
+                ("qtyPrecision".to_string(), precision()),
+                ("pricePrecision".to_string(), precision()),
+                ("totalPrecision".to_string(), precision()),
+                (
+                    "roundMode".to_string(),
+                    FieldSpec::enumeration(&[ROUND_MODE_ROUND, ROUND_MODE_MULTIPLY]),
+                ),
+            ]),
+        ),
     ])
 }
@@ -1,4 +1,6 @@
+pub mod column;
 pub mod djgc;
 pub mod fbfx_csxm;
+pub mod field;
 pub mod gcxm;
 pub mod rcj;
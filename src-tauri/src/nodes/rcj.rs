@@ -4,8 +4,17 @@ use mf_core::node::Node;
 use mf_macro::node;
 use mf_model::schema::AttributeSpec;
 
+use crate::nodes::field::FieldSpec;
+
 pub const RCJ_STR: &str = "rcj";
 
+/// 人材机类型 对应 rcj 节点 type 属性
+pub const RCJ_TYPE_RG: &str = "人工费";
+pub const RCJ_TYPE_CL: &str = "材料费";
+pub const RCJ_TYPE_JX: &str = "机械费";
+pub const RCJ_TYPE_SB: &str = "设备费";
+pub const RCJ_TYPE_ZC: &str = "主材费";
+
 lazy_static! {
     pub static ref RCJ: Node = node!(RCJ_STR, "人材机明细", "");
 }
@@ -36,6 +45,12 @@ fn get_attr_spec() -> HashMap<String, AttributeSpec> {
             default: Some("".into()),
         },
     );
+    att.insert(
+        "unit".to_string(),
+        AttributeSpec {
+            default: Some("".into()),
+        },
+    ); //单位 默认空字符串
     att.insert(
         "specification".to_string(),
         AttributeSpec {
@@ -59,7 +74,13 @@ fn get_attr_spec() -> HashMap<String, AttributeSpec> {
         AttributeSpec {
             default: Some(0.into()),
         },
-    );
+    ); //是否甲供材料 默认0
+    att.insert(
+        "ifProvisionalEstimate".to_string(),
+        AttributeSpec {
+            default: Some(0.into()),
+        },
+    ); //是否暂估材料 默认0
     att.insert(
         "kindBackUp".to_string(),
         AttributeSpec {
@@ -78,6 +99,18 @@ fn get_attr_spec() -> HashMap<String, AttributeSpec> {
             default: Some(0.into()),
         },
     );
+    att.insert(
+        "priceBase".to_string(),
+        AttributeSpec {
+            default: Some(0.into()),
+        },
+    ); //定额基价(不含税) 默认0
+    att.insert(
+        "priceBaseTax".to_string(),
+        AttributeSpec {
+            default: Some(0.into()),
+        },
+    ); //定额基价(含税) 默认0
     att.insert(
         "priceMarket".to_string(),
         AttributeSpec {
@@ -104,3 +137,36 @@ fn get_attr_spec() -> HashMap<String, AttributeSpec> {
     );
     att
 }
+
+/// 人材机明细 属性类型定义
+pub fn init_field_specs() -> HashMap<String, HashMap<String, FieldSpec>> {
+    let price = || FieldSpec::decimal(2).range(Some(0.0), None);
+    let flag = || FieldSpec::integer().range(Some(0.0), Some(1.0));
+    let specs = HashMap::from([
+        ("materialCode".to_string(), FieldSpec::string()),
+        ("materialName".to_string(), FieldSpec::string()),
+        ("unit".to_string(), FieldSpec::string()),
+        ("specification".to_string(), FieldSpec::string()),
+        (
+            "type".to_string(),
+            FieldSpec::enumeration(&[
+                RCJ_TYPE_RG,
+                RCJ_TYPE_CL,
+                RCJ_TYPE_JX,
+                RCJ_TYPE_SB,
+                RCJ_TYPE_ZC,
+            ]),
+        ),
+        ("ifDonorMaterial".to_string(), flag()),
+        ("ifProvisionalEstimate".to_string(), flag()),
+        (
+            "resQty".to_string(),
+            FieldSpec::decimal(6).range(Some(0.0), None),
+        ),
+        ("priceBase".to_string(), price()),
+        ("priceBaseTax".to_string(), price()),
+        ("priceMarket".to_string(), price()),
+        ("priceMarketTax".to_string(), price()),
+    ]);
+    HashMap::from([(RCJ_STR.to_string(), specs)])
+}
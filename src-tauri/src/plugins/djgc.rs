@@ -6,6 +6,9 @@ use mf_state::{plugin::PluginTrait, State, Transaction};
 人材机 数据插入后需要 触发单价构成的计算
 在此方法里 拿到 人材机 的 meta 数据 找到对应的 分部分项节点  新增对应的人材机节点
 并设置 meta 用作 单价构成 插件流转
+目前不回填价格 综合单价在读取时计算(utils::price::qd_unit_cost_with)
+已锁定综合单价的清单、定额 读取时使用锁定快照(utils::price::locked_cost) 不需要在此跳过
+读取时的价格计算均使用 Decimal(utils::money) 插件中没有需要改为 Decimal 的浮点计算
 
 */
 #[derive(Debug)]
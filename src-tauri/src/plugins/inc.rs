@@ -1,47 +1,143 @@
 // 增量数据存储
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
-use mf_model::{attrs::Attrs, mark::Mark, node::Node};
+use mf_model::{mark::Mark, node::Node, node_pool::NodePool};
 use mf_state::{plugin::StateField, resource::Resource, State, StateConfig, Transaction};
 use mf_transform::{
     attr_step::AttrStep,
     mark_step::{AddMarkStep, RemoveMarkStep},
-    node_step::{AddNodeStep, RemoveNodeStep},
+    node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::{
+    node::attrs_map,
+    push::{publish, IncEvent},
+};
+
+/// 增量插件 key 用于从状态中读取变更日志
+pub const INC_PLUGIN_KEY: &str = "inc_plugin";
+/// 变更日志保留的事务数 客户端落后更多时需要全量同步
+const INC_RETENTION: usize = 500;
+
+/// 一个事务产生的增量数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncEntry {
+    pub version: u64,
+    pub operations: Vec<Operation>,
+}
 
-pub struct IncState;
+/// 增量数据 变更日志
+/// 每个产生增量数据的事务 版本号加一 只保留最近 INC_RETENTION 个事务
+#[derive(Debug, Default, Clone)]
+pub struct IncState {
+    pub version: u64,
+    entries: VecDeque<Arc<IncEntry>>,
+}
 impl Resource for IncState {}
+
+/// 增量数据查询结果
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Operations(pub Vec<Operation>);
+pub struct IncFeed {
+    /// 当前版本 下次从该版本开始查询
+    pub version: u64,
+    /// 请求的版本已超出保留范围 需要重新获取全量数据
+    pub resync_required: bool,
+    pub entries: Vec<IncEntry>,
+}
+
+impl IncState {
+    /// 追加一个事务的增量数据
+    fn push(&self, operations: Vec<Operation>) -> Self {
+        let mut next = self.clone();
+        next.version += 1;
+        next.entries.push_back(Arc::new(IncEntry {
+            version: next.version,
+            operations,
+        }));
+        while next.entries.len() > INC_RETENTION {
+            next.entries.pop_front();
+        }
+        next
+    }
+
+    /// 版本号大于 since 的全部增量数据
+    pub fn since(&self, since: u64) -> IncFeed {
+        // 最早可提供的起始版本
+        let oldest = self
+            .entries
+            .front()
+            .map(|entry| entry.version - 1)
+            .unwrap_or(self.version);
+        if since < oldest || since > self.version {
+            return IncFeed {
+                version: self.version,
+                resync_required: true,
+                entries: vec![],
+            };
+        }
+        IncFeed {
+            version: self.version,
+            resync_required: false,
+            entries: self
+                .entries
+                .iter()
+                .filter(|entry| entry.version > since)
+                .map(|entry| entry.as_ref().clone())
+                .collect(),
+        }
+    }
+}
+
+/// 属性变更前后的值 新增属性 old 为 null 删除属性 new 为 null
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AttrChange {
+    pub old: Value,
+    pub new: Value,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum Operation {
     RemoveMark(String, Vec<String>),
     AddMark(String, Vec<Mark>),
-    UpdateAttrs(String, Attrs),
-    UpdateNode(Vec<Arc<Node>>),
+    /// 更新属性 只包含变化的属性 同一事务中对同一节点的多次修改合并为一条
+    UpdateAttrs(String, BTreeMap<String, AttrChange>),
+    /// 新增节点 (父节点 id, 新增节点及其全部下级) 新增节点依次追加到父节点末尾
+    UpdateNode(String, Vec<Arc<Node>>),
     RemoveNode(Vec<String>),
+    /// 移动节点 (节点 id, 新父节点 id, 位置) 不重发子树
+    /// 位置为移出本节点后在新父节点下的序号 为空时追加到末尾 按事务中的顺序依次重放
+    MoveNode(String, String, Option<usize>),
+}
+
+impl Operation {
+    /// 操作涉及的节点 id 用于按子树过滤
+    pub fn node_ids(&self) -> Vec<&String> {
+        match self {
+            Operation::RemoveMark(id, _) | Operation::AddMark(id, _) => vec![id],
+            Operation::UpdateAttrs(id, _) => vec![id],
+            Operation::UpdateNode(_, nodes) => nodes.iter().map(|node| &node.id).collect(),
+            Operation::RemoveNode(ids) => ids.iter().collect(),
+            Operation::MoveNode(id, parent_id, _) => vec![id, parent_id],
+        }
+    }
 }
-impl Resource for Operations {}
-/// 权限状态字段管理器
+/// 增量状态字段管理器
 #[derive(Debug)]
 pub struct IncStateField;
 
-const INC_DATA_KEY: &str = "inc_data";
-
 impl IncStateField {
-    ///收集增量的数据更新
-    pub fn collect_tr(tr: &Transaction, new_state: &State) {
-        if tr.steps.is_empty() {
-            return;
-        }
-        let manager = new_state.resource_manager();
-        let mut operations: Vec<Operation> = Vec::new();
-        //清除老的增量数据
+    ///收集增量的数据更新 old_doc 为事务前的文档 用于计算属性变化
+    pub fn collect_tr(tr: &Transaction, old_doc: &NodePool) -> Vec<Operation> {
+        let doc = tr.doc();
+        let mut collector = OperationCollector::default();
         //收集增量数据
         for (index, step) in tr.steps.iter().enumerate() {
             // 添加节点
@@ -50,16 +146,7 @@ impl IncStateField {
                 for node_enum in add_step.nodes.iter() {
                     node_ids.extend(AddNodeStep::collect_node_ids(node_enum));
                 }
-                let mut nodes = Vec::new();
-                for node_id in node_ids.iter() {
-                    let node = tr.doc().get_node(node_id);
-                    if let Some(node) = node {
-                        nodes.push(node);
-                    }
-                }
-                if nodes.len() > 0 {
-                    operations.push(Operation::UpdateNode(nodes));
-                }
+                collector.add_nodes(&add_step.parent_id, node_ids, &doc);
             }
             // 删除节点
             if let Some(_) = step.downcast_ref::<RemoveNodeStep>() {
@@ -71,24 +158,26 @@ impl IncStateField {
                     }
                 }
                 if node_ids.len() > 0 {
-                    operations.push(Operation::RemoveNode(node_ids));
+                    collector.push(Operation::RemoveNode(node_ids));
                 }
             }
+            // 移动节点 位置取步骤执行时的目标位置 与前后步骤一起按顺序重放
+            if let Some(move_step) = step.downcast_ref::<MoveNodeStep>() {
+                collector.push(Operation::MoveNode(
+                    move_step.node_id.clone(),
+                    move_step.target_parent_id.clone(),
+                    move_step.position,
+                ));
+            }
             // 更新节点
             if let Some(attr_step) = step.downcast_ref::<AttrStep>() {
-                let node = tr.doc().get_node(&attr_step.id);
-                if let Some(node) = node {
-                    operations.push(Operation::UpdateAttrs(
-                        attr_step.id.clone(),
-                        node.attrs.clone(),
-                    ));
-                }
+                collector.update_attrs(&attr_step.id);
             }
             // 添加标记
             if let Some(add_mark_step) = step.downcast_ref::<AddMarkStep>() {
-                let node = tr.doc().get_node(&add_mark_step.id);
+                let node = doc.get_node(&add_mark_step.id);
                 if let Some(_) = node {
-                    operations.push(Operation::AddMark(
+                    collector.push(Operation::AddMark(
                         add_mark_step.id.clone(),
                         add_mark_step.marks.clone(),
                     ));
@@ -96,36 +185,304 @@ impl IncStateField {
             }
             // 删除标记
             if let Some(remove_mark_step) = step.downcast_ref::<RemoveMarkStep>() {
-                let node = tr.doc().get_node(&remove_mark_step.id);
+                let node = doc.get_node(&remove_mark_step.id);
                 if let Some(_) = node {
-                    operations.push(Operation::RemoveMark(
+                    collector.push(Operation::RemoveMark(
                         remove_mark_step.id.clone(),
                         remove_mark_step.mark_types.clone(),
                     ));
                 }
             }
         }
-        if !operations.is_empty() {
-            manager
-                .resource_table
-                .add(INC_DATA_KEY.to_string(), Operations(operations));
+        collector.finish(old_doc, &doc)
+    }
+}
+
+/// 一个事务的增量操作 按步骤顺序记录
+#[derive(Debug, Default)]
+struct OperationCollector {
+    operations: Vec<Operation>,
+    /// 已记录属性更新的节点 同一节点的多次修改合并为一条
+    attr_ids: HashSet<String>,
+    /// 本事务新增的节点 已推送完整节点 不再推送属性变化
+    added: HashSet<String>,
+}
+
+impl OperationCollector {
+    fn push(&mut self, operation: Operation) {
+        self.operations.push(operation);
+    }
+
+    /// 新增节点及其全部下级 doc 为事务后的文档
+    fn add_nodes(&mut self, parent_id: &str, node_ids: Vec<String>, doc: &NodePool) {
+        let mut nodes = Vec::new();
+        for node_id in node_ids {
+            if let Some(node) = doc.get_node(&node_id) {
+                nodes.push(node);
+            }
+            self.added.insert(node_id);
+        }
+        if nodes.len() > 0 {
+            self.push(Operation::UpdateNode(parent_id.to_string(), nodes));
         }
     }
+
+    /// 更新节点 先占位 事务结束后与事务前的属性比较
+    fn update_attrs(&mut self, id: &str) {
+        if !self.added.contains(id) && self.attr_ids.insert(id.to_string()) {
+            self.push(Operation::UpdateAttrs(id.to_string(), BTreeMap::new()));
+        }
+    }
+
+    /// 计算属性变化 去掉属性未变化 或 节点已被删除的属性更新
+    fn finish(mut self, old_doc: &NodePool, doc: &NodePool) -> Vec<Operation> {
+        for operation in self.operations.iter_mut() {
+            if let Operation::UpdateAttrs(id, changes) = operation {
+                if let (Some(old), Some(new)) = (old_doc.get_node(id), doc.get_node(id)) {
+                    *changes = attr_changes(&old, &new);
+                }
+            }
+        }
+        self.operations.retain(|operation| match operation {
+            Operation::UpdateAttrs(_, changes) => !changes.is_empty(),
+            _ => true,
+        });
+        self.operations
+    }
+}
+
+/// 节点属性前后差异
+fn attr_changes(old: &Node, new: &Node) -> BTreeMap<String, AttrChange> {
+    let old_attrs = attrs_map(old);
+    let new_attrs = attrs_map(new);
+    let mut changes = BTreeMap::new();
+    for (key, value) in new_attrs.iter() {
+        let old_value = old_attrs.get(key).cloned().unwrap_or(Value::Null);
+        if &old_value != value {
+            changes.insert(
+                key.clone(),
+                AttrChange {
+                    old: old_value,
+                    new: value.clone(),
+                },
+            );
+        }
+    }
+    for (key, value) in old_attrs.iter() {
+        if !new_attrs.contains_key(key) {
+            changes.insert(
+                key.clone(),
+                AttrChange {
+                    old: value.clone(),
+                    new: Value::Null,
+                },
+            );
+        }
+    }
+    changes
 }
 
 #[async_trait]
 impl StateField for IncStateField {
     async fn init(&self, _config: &StateConfig, _instance: &State) -> Arc<dyn Resource> {
-        Arc::new(IncState)
+        Arc::new(IncState::default())
     }
     async fn apply(
         &self,
         tr: &Transaction,
         value: Arc<dyn Resource>,
-        _old_state: &State,
+        old_state: &State,
         new_state: &State,
     ) -> Arc<dyn Resource> {
-        IncStateField::collect_tr(tr, new_state);
-        value
+        let operations = IncStateField::collect_tr(tr, &old_state.doc());
+        if operations.is_empty() {
+            return value;
+        }
+        match value.downcast_arc::<IncState>() {
+            Ok(state) => {
+                let next = state.push(operations.clone());
+                // 推送给订阅方 协同同步过来的事务同样经过此处
+                let doc = new_state.doc();
+                publish(IncEvent {
+                    editor_name: doc.root_id().clone(),
+                    version: next.version,
+                    operations: Arc::new(operations),
+                    doc,
+                    old_doc: old_state.doc(),
+                });
+                Arc::new(next)
+            }
+            Err(value) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mf_model::{node::Node, node_pool::NodePool, node_type::NodeEnum};
+    use serde_json::{json, Value};
+
+    use super::{attr_changes, AttrChange, IncState, Operation, OperationCollector, INC_RETENTION};
+
+    fn state(count: usize) -> IncState {
+        (0..count).fold(IncState::default(), |state, index| {
+            state.push(vec![Operation::RemoveNode(vec![format!("n{}", index)])])
+        })
+    }
+
+    fn versions(state: &IncState, since: u64) -> Vec<u64> {
+        let feed = state.since(since);
+        assert!(!feed.resync_required);
+        assert_eq!(feed.version, state.version);
+        feed.entries.iter().map(|entry| entry.version).collect()
+    }
+
+    #[test]
+    fn since_oldest_returns_all_entries() {
+        let state = state(3);
+        assert_eq!(versions(&state, 0), vec![1, 2, 3]);
+        assert_eq!(versions(&state, 2), vec![3]);
+        assert!(versions(&state, 3).is_empty());
+    }
+
+    #[test]
+    fn since_before_oldest_requires_resync() {
+        let state = state(INC_RETENTION + 2);
+        // 版本 1、2 已被移出 最早可从版本 2 开始查询
+        assert_eq!(versions(&state, 2).len(), INC_RETENTION);
+        let feed = state.since(1);
+        assert!(feed.resync_required);
+        assert!(feed.entries.is_empty());
+    }
+
+    #[test]
+    fn since_after_version_requires_resync() {
+        let state = state(3);
+        let feed = state.since(4);
+        assert!(feed.resync_required);
+        assert_eq!(feed.version, 3);
+        assert!(IncState::default().since(1).resync_required);
+        assert!(!IncState::default().since(0).resync_required);
+    }
+
+    #[test]
+    fn push_trims_to_retention() {
+        let state = state(INC_RETENTION + 2);
+        assert_eq!(state.version, INC_RETENTION as u64 + 2);
+        assert_eq!(state.entries.len(), INC_RETENTION);
+        assert_eq!(state.entries.front().unwrap().version, 3);
+        assert_eq!(
+            state.entries.back().unwrap().version,
+            INC_RETENTION as u64 + 2
+        );
+    }
+
+    fn node(id: &str, attrs: Value) -> Node {
+        Node::new(
+            id,
+            "FB".to_string(),
+            serde_json::from_value(attrs).unwrap(),
+            vec![],
+            vec![],
+        )
+    }
+
+    /// root 下依次为 children
+    fn doc(children: Vec<Node>) -> NodePool {
+        let content = children.iter().map(|child| child.id.clone()).collect();
+        NodePool::from(NodeEnum(
+            Node::new(
+                "root",
+                "FB".to_string(),
+                serde_json::from_value(json!({})).unwrap(),
+                content,
+                vec![],
+            ),
+            children
+                .into_iter()
+                .map(|child| NodeEnum(child, vec![]))
+                .collect(),
+        ))
+        .as_ref()
+        .clone()
+    }
+
+    fn change(old: Value, new: Value) -> AttrChange {
+        AttrChange { old, new }
+    }
+
+    #[test]
+    fn attr_steps_on_one_node_coalesce() {
+        let old_doc = doc(vec![
+            node("a", json!({ "name": "a", "quantity": "1" })),
+            node("b", json!({ "name": "b" })),
+        ]);
+        let new_doc = doc(vec![
+            node("a", json!({ "name": "a2", "quantity": "2" })),
+            node("b", json!({ "name": "b" })),
+        ]);
+        let mut collector = OperationCollector::default();
+        collector.update_attrs("a");
+        collector.push(Operation::MoveNode(
+            "b".to_string(),
+            "root".to_string(),
+            None,
+        ));
+        collector.update_attrs("a");
+        // 属性改回原值 不推送
+        collector.update_attrs("b");
+        let operations = collector.finish(&old_doc, &new_doc);
+        assert_eq!(operations.len(), 2);
+        match &operations[0] {
+            Operation::UpdateAttrs(id, changes) => {
+                assert_eq!(id, "a");
+                assert_eq!(changes.len(), 2);
+                assert_eq!(changes["name"], change(json!("a"), json!("a2")));
+                assert_eq!(changes["quantity"], change(json!("1"), json!("2")));
+            }
+            operation => panic!("unexpected {:?}", operation),
+        }
+        assert!(matches!(operations[1], Operation::MoveNode(..)));
+    }
+
+    #[test]
+    fn added_nodes_skip_attr_updates() {
+        let old_doc = doc(vec![]);
+        let new_doc = doc(vec![node("a", json!({ "name": "a" }))]);
+        let mut collector = OperationCollector::default();
+        collector.add_nodes("root", vec!["a".to_string()], &new_doc);
+        collector.update_attrs("a");
+        let operations = collector.finish(&old_doc, &new_doc);
+        assert_eq!(operations.len(), 1);
+        match &operations[0] {
+            Operation::UpdateNode(parent_id, nodes) => {
+                assert_eq!(parent_id, "root");
+                assert_eq!(nodes.len(), 1);
+                assert_eq!(nodes[0].id, "a");
+            }
+            operation => panic!("unexpected {:?}", operation),
+        }
+    }
+
+    #[test]
+    fn removed_attrs_are_null() {
+        let old = node("a", json!({ "name": "a", "remark": "r" }));
+        let new = node("a", json!({ "name": "a", "unit": "m3" }));
+        let changes = attr_changes(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["remark"], change(json!("r"), Value::Null));
+        assert_eq!(changes["unit"], change(Value::Null, json!("m3")));
+    }
+
+    #[test]
+    fn removed_nodes_drop_attr_updates() {
+        let old_doc = doc(vec![node("a", json!({ "name": "a" }))]);
+        let new_doc = doc(vec![]);
+        let mut collector = OperationCollector::default();
+        collector.update_attrs("a");
+        collector.push(Operation::RemoveNode(vec!["a".to_string()]));
+        let operations = collector.finish(&old_doc, &new_doc);
+        assert_eq!(operations.len(), 1);
+        assert!(matches!(operations[0], Operation::RemoveNode(_)));
     }
 }
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use mf_state::{plugin::PluginTrait, State, Transaction};
+use mf_transform::{
+    attr_step::AttrStep,
+    node_step::{AddNodeStep, MoveNodeStep, RemoveNodeStep},
+};
+
+use crate::utils::price::{check_children_unlocked, check_unlocked};
+
+/*
+锁定综合单价 插件
+拒绝修改 已锁定的清单、定额 组成(下级定额、人材机、单价构成)的事务
+命令中已提前检查并返回错误信息 此处兜底 例如协同同步过来的事务
+*/
+#[derive(Debug)]
+pub struct LockPlugin;
+
+impl LockPlugin {
+    /// 检查事务是否修改了锁定行的组成
+    pub fn check(tr: &Transaction, state: &State) -> Result<(), String> {
+        let doc = state.doc();
+        for step in tr.steps.iter() {
+            if let Some(attr_step) = step.downcast_ref::<AttrStep>() {
+                check_unlocked(&doc, &attr_step.id)?;
+            }
+            if let Some(add_step) = step.downcast_ref::<AddNodeStep>() {
+                check_children_unlocked(&doc, &add_step.parent_id)?;
+            }
+            if let Some(remove_step) = step.downcast_ref::<RemoveNodeStep>() {
+                check_children_unlocked(&doc, &remove_step.parent_id)?;
+            }
+            if let Some(move_step) = step.downcast_ref::<MoveNodeStep>() {
+                check_children_unlocked(&doc, &move_step.source_parent_id)?;
+                check_children_unlocked(&doc, &move_step.target_parent_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PluginTrait for LockPlugin {
+    async fn filter_transaction(&self, tr: &Transaction, state: &State) -> bool {
+        match Self::check(tr, state) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("事务被拒绝: {}", e);
+                false
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 pub mod djgc;
 pub mod fbfx_csxm;
 pub mod inc;
+pub mod lock;
 pub mod rcj;
+pub mod validate;
 pub mod collab;
\ No newline at end of file
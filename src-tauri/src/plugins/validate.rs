@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use mf_model::types::NodeId;
+use mf_state::{plugin::PluginTrait, State, Transaction};
+use mf_transform::{attr_step::AttrStep, node_step::AddNodeStep};
+use serde_json::Value;
+
+use crate::{
+    nodes::{
+        column::{column_specs, custom_columns, CUSTOM_COLUMN_TYPES},
+        field::{field_spec, FieldError, FieldErrors, FieldSpec},
+    },
+    utils::node::attrs_map,
+};
+
+/*
+属性校验 插件
+拒绝写入不符合属性类型定义(含工程项目自定义列)的值 例如工程量不是数字、枚举值不在范围内
+修改的属性 与 新增节点(含下级)的全部属性 都会校验
+命令中已提前校验并转换 此处兜底 例如直接调用 set_node_attribute、add_node 或协同同步过来的事务
+*/
+#[derive(Debug)]
+pub struct ValidatePlugin;
+
+impl ValidatePlugin {
+    /// 校验节点的属性值 custom 为工程项目的自定义列属性定义
+    fn check_values<'a>(
+        id: &NodeId,
+        node_type: &str,
+        values: impl Iterator<Item = (&'a String, &'a Value)>,
+        custom: &HashMap<String, FieldSpec>,
+        errors: &mut Vec<FieldError>,
+    ) {
+        let custom = CUSTOM_COLUMN_TYPES.contains(&node_type).then_some(custom);
+        for (key, value) in values {
+            let spec = field_spec(node_type, key).or_else(|| custom.and_then(|c| c.get(key)));
+            if let Some(spec) = spec {
+                if let Err(message) = spec.coerce(value) {
+                    errors.push(FieldError {
+                        id: id.clone(),
+                        key: key.clone(),
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    /// 校验事务中修改的属性 及新增节点的属性
+    pub fn check(tr: &Transaction) -> Result<(), FieldErrors> {
+        let doc = tr.doc();
+        // 工程项目的自定义列 整个事务只读取一次
+        let custom = column_specs(&custom_columns(&doc));
+        let mut errors = Vec::new();
+        for step in tr.steps.iter() {
+            if let Some(attr_step) = step.downcast_ref::<AttrStep>() {
+                let node = match doc.get_node(&attr_step.id) {
+                    Some(node) => node,
+                    None => continue,
+                };
+                Self::check_values(
+                    &attr_step.id,
+                    &node.r#type,
+                    attr_step.values.iter(),
+                    &custom,
+                    &mut errors,
+                );
+            }
+            // 新增节点 例如导入、粘贴 属性不经过 AttrStep
+            if let Some(add_step) = step.downcast_ref::<AddNodeStep>() {
+                for node_enum in add_step.nodes.iter() {
+                    for id in AddNodeStep::collect_node_ids(node_enum) {
+                        if let Some(node) = doc.get_node(&id) {
+                            let attrs = attrs_map(&node);
+                            Self::check_values(
+                                &id,
+                                &node.r#type,
+                                attrs.iter(),
+                                &custom,
+                                &mut errors,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(FieldErrors(errors))
+        }
+    }
+}
+
+#[async_trait]
+impl PluginTrait for ValidatePlugin {
+    async fn filter_transaction(&self, tr: &Transaction, _state: &State) -> bool {
+        match Self::check(tr) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("事务被拒绝: {}", e);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde_json::{json, Value};
+
+use crate::report::{
+    model::ReportData,
+    template::{
+        escape_value, list_templates, load_source, render_template, ReportTemplate, TemplateScope,
+    },
+};
+
+pub mod model;
+pub mod pdf;
+pub mod template;
+
+/// 合并上下文 page 中的变量覆盖 context 中的同名变量
+fn merge(context: &Value, page: Value) -> Value {
+    let mut merged = context.clone();
+    if let (Value::Object(merged), Value::Object(page)) = (&mut merged, page) {
+        merged.extend(page);
+    }
+    merged
+}
+
+/// 渲染一页报表 页眉放在 thead 中 打印时每页重复
+fn render_page(template: &ReportTemplate, header: &str, context: &Value) -> anyhow::Result<String> {
+    let body = render_template(&template.body, context)?;
+    let header = if template.header {
+        render_template(header, context)?
+    } else {
+        String::new()
+    };
+    Ok(format!(
+        "<table class=\"report-page\"><thead><tr><td>{}</td></tr></thead><tbody><tr><td>{}</td></tr></tbody></table>\n",
+        header, body
+    ))
+}
+
+/// 按模板生成报表 HTML names 为空时输出全部模板
+/// 上下文中的文本先转义 模板按原样输出
+pub fn render_html(doc: &NodePool, id: &NodeId, names: &[String]) -> anyhow::Result<String> {
+    let data = ReportData::build(doc, id)?;
+    let mut context = serde_json::to_value(&data)?;
+    escape_value(&mut context);
+    let templates: Vec<ReportTemplate> = if names.is_empty() {
+        list_templates()?
+    } else {
+        names
+            .iter()
+            .map(|name| Ok(ReportTemplate::parse(name, &load_source(name)?)))
+            .collect::<anyhow::Result<_>>()?
+    };
+    let header = load_source("_header")?;
+    let dwgcs = match context.get("dwgcs") {
+        Some(Value::Array(dwgcs)) => dwgcs.clone(),
+        _ => vec![],
+    };
+    let mut content = String::new();
+    for template in templates.iter() {
+        let mut title = Value::from(template.title.clone());
+        escape_value(&mut title);
+        match template.scope {
+            TemplateScope::Project => {
+                let page = merge(&context, json!({ "title": title, "dwgc": null }));
+                content.push_str(&render_page(template, &header, &page)?);
+            }
+            TemplateScope::Dwgc => {
+                for dwgc in dwgcs.iter() {
+                    let page = merge(&context, json!({ "title": title, "dwgc": dwgc }));
+                    content.push_str(&render_page(template, &header, &page)?);
+                }
+            }
+        }
+    }
+    let layout = merge(&context, json!({ "content": content }));
+    render_template(&load_source("_layout")?, &layout)
+}
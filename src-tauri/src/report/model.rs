@@ -0,0 +1,473 @@
+use chrono::Local;
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde::Serialize;
+
+use crate::{
+    controller::{
+        zhdjfx::{ZhdjfxDeRow, ZhdjfxMaterialRow, ZhdjfxTable},
+        GcxmTreeItem,
+    },
+    nodes::{
+        fbfx_csxm::{CSXM_STR, FBFX_STR, FB_STR, QD_STR},
+        gcxm::{DWGC_STR, DXGC_STR, GCXM_STR},
+    },
+    utils::{
+        dxje::rmb_upper,
+        fyhz::{gczj_total, Fyhz, XmhzItem},
+        money::{get_decimal, round, to_f64, Decimal},
+        node::{children_of_type, descendants_of_type, find_ancestor, get_str},
+        price::{qd_amount, PriceOptions, UnitCost},
+    },
+};
+
+/// 总说明 未填写时输出的内容
+const DEFAULT_DESCRIPTION: &str = "1. 工程概况：
+
+2. 编制依据：《建设工程工程量清单计价规范》GB 50500 及相关计价依据。
+
+3. 其他需要说明的问题：";
+
+/// 金额 保留 2 位小数
+fn money(value: Decimal) -> String {
+    round(value, 2).to_string()
+}
+
+/// 金额 为 0 时为空
+fn money_or_blank(value: Decimal) -> String {
+    if value.is_zero() {
+        String::new()
+    } else {
+        money(value)
+    }
+}
+
+/// 工程量 保留 3 位小数
+fn quantity(value: Decimal) -> String {
+    round(value, 3).to_string()
+}
+
+/// 含量、消耗量 保留 4 位小数
+fn ratio(value: Decimal) -> String {
+    round(value, 4).to_string()
+}
+
+/// 人民币大写
+fn upper(value: Decimal) -> String {
+    rmb_upper(to_f64(round(value, 2)))
+}
+
+/// 费用汇总行
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportFyhzRow {
+    pub index: String,
+    pub code: String,
+    pub name: String,
+    pub base: String,
+    pub amount: String,
+    pub in_total: bool,
+}
+
+/// 计价表行 分部标题、清单、分部小计
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportQdRow {
+    /// fb / qd / subtotal
+    pub kind: &'static str,
+    /// 清单序号 分部行为空
+    pub seq: String,
+    pub project_code: String,
+    pub project_name: String,
+    pub project_attr: String,
+    pub unit: String,
+    pub quantity: String,
+    pub price: String,
+    pub total: String,
+    /// 暂估价 为 0 时为空
+    pub zg_total: String,
+}
+
+/// 单价构成 人工费、材料费、机械费、设备费、主材费、管理费、利润
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportUnitCost {
+    pub rgf: String,
+    pub clf: String,
+    pub jxf: String,
+    pub sbf: String,
+    pub zcf: String,
+    pub glf: String,
+    pub lr: String,
+}
+
+/// 综合单价分析表 定额行
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportZhdjfxDe {
+    pub project_code: String,
+    pub project_name: String,
+    pub unit: String,
+    pub ratio: String,
+    pub unit_cost: ReportUnitCost,
+    pub price: String,
+    pub total: String,
+}
+
+/// 综合单价分析表 主要材料行
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportZhdjfxMaterial {
+    pub material_name: String,
+    pub specification: String,
+    pub unit: String,
+    pub res_qty: String,
+    pub price: String,
+    pub total: String,
+    /// 备注 暂估、甲供
+    pub remark: String,
+}
+
+/// 综合单价分析表 每条清单一张
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportZhdjfx {
+    pub project_code: String,
+    pub project_name: String,
+    pub unit: String,
+    pub quantity: String,
+    pub unit_cost: ReportUnitCost,
+    pub price: String,
+    pub de_rows: Vec<ReportZhdjfxDe>,
+    pub materials: Vec<ReportZhdjfxMaterial>,
+}
+
+/// 工程项目汇总表行
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportXmhzRow {
+    pub index: String,
+    /// 层级 汇总起点为 0
+    pub level: String,
+    pub name: String,
+    pub fbfx: String,
+    pub csxm: String,
+    pub qtxm: String,
+    pub gf: String,
+    pub sj: String,
+    pub total: String,
+    /// 占比(%)
+    pub share: String,
+}
+
+/// 单位工程报表数据
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportDwgc {
+    pub id: NodeId,
+    pub code: String,
+    pub name: String,
+    /// 所属单项工程名称
+    pub dxgc_name: String,
+    pub fbfx_total: String,
+    pub csxm_total: String,
+    pub zg_total: String,
+    pub total: String,
+    /// 工程造价大写
+    pub total_upper: String,
+    pub fyhz: Vec<ReportFyhzRow>,
+    pub fbfx: Vec<ReportQdRow>,
+    pub csxm: Vec<ReportQdRow>,
+    pub zhdjfx: Vec<ReportZhdjfx>,
+}
+
+/// 报表数据 模板渲染的上下文
+/// 金额、工程量按单位工程取整规则计算后格式化为字符串 模板直接输出
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportData {
+    pub id: NodeId,
+    /// 导出节点类型 GCXM / DXGC / DWGC
+    pub r#type: String,
+    pub code: String,
+    pub name: String,
+    /// 所属工程项目名称
+    pub project_name: String,
+    /// 总说明 未填写时为默认内容
+    pub description: String,
+    pub total: String,
+    /// 工程造价大写
+    pub total_upper: String,
+    /// 编制日期
+    pub date: String,
+    /// 工程项目汇总表 按先序展开的各级汇总行
+    pub xmhz: Vec<ReportXmhzRow>,
+    pub dwgcs: Vec<ReportDwgc>,
+}
+
+impl ReportUnitCost {
+    fn of(cost: &UnitCost) -> Self {
+        Self {
+            rgf: money(cost.rgf),
+            clf: money(cost.clf),
+            jxf: money(cost.jxf),
+            sbf: money(cost.sbf),
+            zcf: money(cost.zcf),
+            glf: money(cost.glf),
+            lr: money(cost.lr),
+        }
+    }
+}
+
+impl ReportZhdjfxDe {
+    fn of(row: &ZhdjfxDeRow) -> Self {
+        Self {
+            project_code: row.project_code.clone(),
+            project_name: row.project_name.clone(),
+            unit: row.unit.clone(),
+            ratio: ratio(row.ratio),
+            unit_cost: ReportUnitCost::of(&row.unit_cost),
+            price: money(row.price),
+            total: money(row.total),
+        }
+    }
+}
+
+impl ReportZhdjfxMaterial {
+    fn of(row: &ZhdjfxMaterialRow) -> Self {
+        let mut remark = String::new();
+        if row.zg {
+            remark.push_str("暂估");
+        }
+        if row.jg {
+            remark.push_str("甲供");
+        }
+        Self {
+            material_name: row.material_name.clone(),
+            specification: row.specification.clone(),
+            unit: row.unit.clone(),
+            res_qty: ratio(row.res_qty),
+            price: money(row.price),
+            total: money(row.total),
+            remark,
+        }
+    }
+}
+
+impl ReportZhdjfx {
+    fn of(table: &ZhdjfxTable) -> Self {
+        Self {
+            project_code: table.project_code.clone(),
+            project_name: table.project_name.clone(),
+            unit: table.unit.clone(),
+            quantity: quantity(table.quantity),
+            unit_cost: ReportUnitCost::of(&table.unit_cost),
+            price: money(table.price),
+            de_rows: table.de_rows.iter().map(ReportZhdjfxDe::of).collect(),
+            materials: table
+                .materials
+                .iter()
+                .map(ReportZhdjfxMaterial::of)
+                .collect(),
+        }
+    }
+}
+
+impl ReportXmhzRow {
+    fn of(index: usize, item: &XmhzItem) -> Self {
+        Self {
+            index: (index + 1).to_string(),
+            level: item.level.to_string(),
+            name: item.name.clone(),
+            fbfx: money(item.fbfx),
+            csxm: money(item.csxm),
+            qtxm: money(item.qtxm),
+            gf: money(item.gf),
+            sj: money(item.sj),
+            total: money(item.total),
+            share: money(item.share),
+        }
+    }
+}
+
+impl ReportQdRow {
+    fn fb(node: &Node) -> Self {
+        Self {
+            kind: "fb",
+            seq: String::new(),
+            project_code: get_str(node, "projectCode"),
+            project_name: get_str(node, "projectName"),
+            project_attr: String::new(),
+            unit: String::new(),
+            quantity: String::new(),
+            price: String::new(),
+            total: String::new(),
+            zg_total: String::new(),
+        }
+    }
+
+    fn subtotal(name: &str, total: (Decimal, Decimal)) -> Self {
+        Self {
+            kind: "subtotal",
+            seq: String::new(),
+            project_code: String::new(),
+            project_name: name.to_string(),
+            project_attr: String::new(),
+            unit: String::new(),
+            quantity: String::new(),
+            price: String::new(),
+            total: money(total.0),
+            zg_total: money(total.1),
+        }
+    }
+}
+
+/// 展开分部、清单行 返回 (合价, 暂估价) 合计
+fn push_qd_rows(
+    rows: &mut Vec<ReportQdRow>,
+    doc: &NodePool,
+    item: &GcxmTreeItem,
+    options: &PriceOptions,
+    seq: &mut usize,
+) -> (Decimal, Decimal) {
+    let mut total = (Decimal::ZERO, Decimal::ZERO);
+    for child in item.children.iter() {
+        let node = match doc.get_node(&child.id) {
+            Some(node) => node,
+            None => continue,
+        };
+        if child.r#type == FB_STR {
+            rows.push(ReportQdRow::fb(&node));
+            let sub = push_qd_rows(rows, doc, child, options, seq);
+            rows.push(ReportQdRow::subtotal("分部小计", sub));
+            total.0 += sub.0;
+            total.1 += sub.1;
+        } else if child.r#type == QD_STR {
+            *seq += 1;
+            let amount = qd_amount(doc, &node, options);
+            let zg_total = get_decimal(&node, "zgfTotal");
+            rows.push(ReportQdRow {
+                kind: "qd",
+                seq: seq.to_string(),
+                project_code: get_str(&node, "projectCode"),
+                project_name: get_str(&node, "projectName"),
+                project_attr: get_str(&node, "projectAttr"),
+                unit: get_str(&node, "unit"),
+                quantity: quantity(amount.quantity),
+                price: money(amount.price),
+                total: money(amount.total),
+                zg_total: money_or_blank(zg_total),
+            });
+            total.0 += amount.total;
+            total.1 += zg_total;
+        }
+    }
+    total
+}
+
+/// 分部分项 或 措施项目 计价表行 返回 (行, 合价, 暂估价)
+fn qd_rows(
+    doc: &NodePool,
+    dwgc_id: &NodeId,
+    root_type: &str,
+) -> (Vec<ReportQdRow>, Decimal, Decimal) {
+    let options = PriceOptions::of(doc, dwgc_id);
+    let mut rows = Vec::new();
+    let mut seq = 0;
+    let mut total = (Decimal::ZERO, Decimal::ZERO);
+    for root in children_of_type(doc, dwgc_id, root_type) {
+        if let Some(tree) = GcxmTreeItem::fbfx_csxm_tree(doc, &root.id) {
+            let sub = push_qd_rows(&mut rows, doc, &tree, &options, &mut seq);
+            total.0 += sub.0;
+            total.1 += sub.1;
+        }
+    }
+    (rows, total.0, total.1)
+}
+
+impl ReportDwgc {
+    pub fn build(doc: &NodePool, dwgc: &Node) -> Self {
+        let fyhz = Fyhz::build(doc, &dwgc.id);
+        let (fbfx, fbfx_total, fbfx_zg) = qd_rows(doc, &dwgc.id, FBFX_STR);
+        let (csxm, csxm_total, csxm_zg) = qd_rows(doc, &dwgc.id, CSXM_STR);
+        let total = fyhz.as_ref().map(|fyhz| fyhz.total).unwrap_or_default();
+        Self {
+            id: dwgc.id.clone(),
+            code: get_str(dwgc, "code"),
+            name: get_str(dwgc, "name"),
+            dxgc_name: find_ancestor(doc, &dwgc.id, DXGC_STR)
+                .map(|dxgc| get_str(&dxgc, "name"))
+                .unwrap_or_default(),
+            fbfx_total: money(fbfx_total),
+            csxm_total: money(csxm_total),
+            zg_total: money(fbfx_zg + csxm_zg),
+            total: money(total),
+            total_upper: upper(total),
+            fyhz: fyhz
+                .map(|fyhz| {
+                    fyhz.rows
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, row)| ReportFyhzRow {
+                            index: (index + 1).to_string(),
+                            code: row.code,
+                            name: row.name,
+                            base: row.base,
+                            amount: money(row.amount),
+                            in_total: row.in_total,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            fbfx,
+            csxm,
+            zhdjfx: ZhdjfxTable::build_all(doc, &dwgc.id)
+                .iter()
+                .map(ReportZhdjfx::of)
+                .collect(),
+        }
+    }
+}
+
+impl ReportData {
+    /// 构建 工程项目、单项工程 或 单位工程 的报表数据
+    pub fn build(doc: &NodePool, id: &NodeId) -> anyhow::Result<Self> {
+        let node = match doc.get_node(id) {
+            Some(node) => node,
+            None => return Err(anyhow::anyhow!("节点不存在".to_string())),
+        };
+        let dwgcs: Vec<ReportDwgc> = if node.r#type == DWGC_STR {
+            vec![ReportDwgc::build(doc, &node)]
+        } else {
+            descendants_of_type(doc, id, &[DWGC_STR])
+                .iter()
+                .map(|dwgc| ReportDwgc::build(doc, dwgc))
+                .collect()
+        };
+        let project = if node.r#type == GCXM_STR {
+            Some(node.clone())
+        } else {
+            find_ancestor(doc, id, GCXM_STR)
+        };
+        let total = gczj_total(doc, id);
+        let description = project
+            .as_ref()
+            .map(|project| get_str(project, "description"))
+            .filter(|description| !description.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_DESCRIPTION.to_string());
+        Ok(Self {
+            id: node.id.clone(),
+            r#type: node.r#type.to_string(),
+            code: get_str(&node, "code"),
+            name: get_str(&node, "name"),
+            project_name: project
+                .as_ref()
+                .map(|project| get_str(project, "name"))
+                .unwrap_or_default(),
+            description,
+            total: money(total),
+            total_upper: upper(total),
+            date: Local::now().format("%Y-%m-%d").to_string(),
+            xmhz: XmhzItem::build(doc, id)
+                .map(|xmhz| {
+                    xmhz.flatten()
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| ReportXmhzRow::of(index, item))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            dwgcs,
+        })
+    }
+}
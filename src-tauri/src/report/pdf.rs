@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use mf_model::id_generator::IdGenerator;
+use tokio::process::Command;
+
+/// 常见的 Chromium 内核浏览器位置
+const BROWSER_CANDIDATES: &[&str] = &[
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+    r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    "/usr/bin/google-chrome",
+    "/usr/bin/chromium",
+    "/usr/bin/chromium-browser",
+    "/usr/bin/microsoft-edge",
+];
+
+/// 查找用于打印 PDF 的浏览器 可通过环境变量 MODUFORGE_PDF_BROWSER 指定
+fn find_browser() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("MODUFORGE_PDF_BROWSER") {
+        return Some(PathBuf::from(path));
+    }
+    BROWSER_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+}
+
+/// HTML 转 PDF 使用无头浏览器打印 与浏览器中打印预览的效果一致
+pub async fn html_to_pdf(html: &str) -> anyhow::Result<Vec<u8>> {
+    let browser = match find_browser() {
+        Some(browser) => browser,
+        None => {
+            return Err(anyhow::anyhow!(
+            "未找到可用于生成 PDF 的浏览器 请安装 Edge/Chrome 或设置环境变量 MODUFORGE_PDF_BROWSER"
+                .to_string()
+        ))
+        }
+    };
+    let dir = std::env::temp_dir().join("moduforge-report");
+    tokio::fs::create_dir_all(&dir).await?;
+    let id = IdGenerator::get_id();
+    let html_path = dir.join(format!("{}.html", id));
+    let pdf_path = dir.join(format!("{}.pdf", id));
+    tokio::fs::write(&html_path, html).await?;
+
+    let output = Command::new(&browser)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--print-to-pdf={}", pdf_path.display()))
+        .arg(&html_path)
+        .output()
+        .await;
+    let result = match output {
+        Ok(_) if pdf_path.exists() => Ok(tokio::fs::read(&pdf_path).await?),
+        Ok(output) => Err(anyhow::anyhow!(
+            "生成 PDF 失败: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )),
+        Err(e) => Err(anyhow::anyhow!(
+            "启动浏览器 {} 失败: {}",
+            browser.display(),
+            e
+        )),
+    };
+    let _ = tokio::fs::remove_file(&html_path).await;
+    let _ = tokio::fs::remove_file(&pdf_path).await;
+    result
+}
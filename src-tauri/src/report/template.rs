@@ -0,0 +1,209 @@
+use std::{fs, path::PathBuf};
+
+use mf_template::render;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::local_library::local_data_dir;
+
+/// 内置模板 启动时写入模板目录 用户可直接修改或新增模板文件
+/// 以 _ 开头的为公共部分 不作为报表列出
+const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    ("_layout", include_str!("../../templates/_layout.html")),
+    ("_header", include_str!("../../templates/_header.html")),
+    ("cover", include_str!("../../templates/cover.html")),
+    ("zsm", include_str!("../../templates/zsm.html")),
+    ("xmhz", include_str!("../../templates/xmhz.html")),
+    ("dwgchz", include_str!("../../templates/dwgchz.html")),
+    ("fbfx", include_str!("../../templates/fbfx.html")),
+    ("zhdjfx", include_str!("../../templates/zhdjfx.html")),
+];
+
+/// 模板渲染范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateScope {
+    /// 整个报表渲染一次
+    Project,
+    /// 每个单位工程渲染一次 上下文中增加 dwgc
+    Dwgc,
+}
+
+/// 报表模板
+/// 文件开头可用注释声明模板信息 例如
+/// <!--
+/// title: 分部分项工程量清单计价表
+/// scope: dwgc
+/// order: 40
+/// header: true
+/// -->
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportTemplate {
+    /// 模板名 即文件名(不含扩展名)
+    pub name: String,
+    pub title: String,
+    pub scope: TemplateScope,
+    /// 打印顺序
+    pub order: i32,
+    /// 是否输出页眉
+    pub header: bool,
+    /// 是否为内置模板
+    pub builtin: bool,
+    #[serde(skip)]
+    pub body: String,
+}
+
+impl ReportTemplate {
+    /// 解析模板文件 读取开头注释中的模板信息
+    pub fn parse(name: &str, source: &str) -> Self {
+        let mut template = Self {
+            name: name.to_string(),
+            title: name.to_string(),
+            scope: TemplateScope::Project,
+            order: 100,
+            header: true,
+            builtin: DEFAULT_TEMPLATES.iter().any(|(n, _)| *n == name),
+            body: source.to_string(),
+        };
+        let trimmed = source.trim_start();
+        let front = match trimmed.strip_prefix("<!--") {
+            Some(rest) => rest,
+            None => return template,
+        };
+        let end = match front.find("-->") {
+            Some(end) => end,
+            None => return template,
+        };
+        for line in front[..end].lines() {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "title" => template.title = value.to_string(),
+                "scope" => {
+                    if value == "dwgc" {
+                        template.scope = TemplateScope::Dwgc;
+                    }
+                }
+                "order" => template.order = value.parse().unwrap_or(template.order),
+                "header" => template.header = value != "false",
+                _ => {}
+            }
+        }
+        template.body = front[end + 3..].to_string();
+        template
+    }
+}
+
+/// 报表模板目录 {本地库根目录}/templates
+pub fn template_dir() -> PathBuf {
+    local_data_dir().join("templates")
+}
+
+/// 模板名只允许字母、数字、下划线、短横线 防止跳出模板目录
+fn check_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("报表模板名 {} 无效", name))
+    }
+}
+
+/// 把缺失的内置模板写入模板目录 已存在的文件不覆盖 启动时调用一次
+pub fn init_templates() {
+    let dir = template_dir();
+    if fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    for (name, source) in DEFAULT_TEMPLATES.iter() {
+        let path = dir.join(format!("{}.html", name));
+        if !path.exists() {
+            if let Err(e) = fs::write(&path, source) {
+                tracing::warn!("写入内置模板 {} 失败: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 读取模板源码 优先使用模板目录中的文件 其次为内置模板
+pub fn load_source(name: &str) -> anyhow::Result<String> {
+    check_name(name)?;
+    let path = template_dir().join(format!("{}.html", name));
+    if path.exists() {
+        return Ok(fs::read_to_string(&path)?);
+    }
+    DEFAULT_TEMPLATES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, source)| source.to_string())
+        .ok_or_else(|| anyhow::anyhow!("报表模板 {} 不存在", name))
+}
+
+/// 列出全部报表模板 按打印顺序排列
+pub fn list_templates() -> anyhow::Result<Vec<ReportTemplate>> {
+    let mut names: Vec<String> = DEFAULT_TEMPLATES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect();
+    if let Ok(entries) = fs::read_dir(template_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("html") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                if check_name(name).is_ok() && !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    let mut templates = Vec::new();
+    for name in names.iter().filter(|name| !name.starts_with('_')) {
+        templates.push(ReportTemplate::parse(name, &load_source(name)?));
+    }
+    templates.sort_by(|a, b| a.order.cmp(&b.order).then(a.name.cmp(&b.name)));
+    Ok(templates)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// 转义上下文中的全部字符串 模板输出时不再转义
+pub fn escape_value(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = escape_html(text),
+        Value::Array(items) => items.iter_mut().for_each(escape_value),
+        Value::Object(object) => object.values_mut().for_each(escape_value),
+        _ => {}
+    }
+}
+
+/// 渲染模板 表达式由 mf_template 求值 列表用 map/join 展开 条件用 ? :
+/// 例如 {{ join(map(dwgc.fyhz, '<tr><td>' + #.name + '</td></tr>'), '') }}
+pub fn render_template(source: &str, context: &Value) -> anyhow::Result<String> {
+    let result = render(source, context.clone().into())
+        .map_err(|e| anyhow::anyhow!("报表模板渲染失败: {:?}", e))?;
+    Ok(match serde_json::to_value(&result)? {
+        Value::String(text) => text,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    })
+}
@@ -1,9 +1,25 @@
 use axum::Router;
 
-use crate::controller::{fbfx_csxm, gcxm};
+use crate::controller::{
+    bc, clipboard, drqd, exchange, export, fbfx_csxm, fyhz, gcxm, rcj, report, schema, tj, xmbm,
+    zhdjfx, zjfa,
+};
 
 pub fn build_app() -> Router {
     Router::new()
         .nest("/gcxm", gcxm::build_app()) //工程项目
         .nest("/fbfx_csxm", fbfx_csxm::build_app()) //分部分项 措施项目
+        .nest("/zhdjfx", zhdjfx::build_app()) //综合单价分析
+        .nest("/bc", bc::build_app()) //补充定额 补充人材机
+        .nest("/zjfa", zjfa::build_app()) //组价方案
+        .nest("/rcj", rcj::build_app()) //人材机汇总
+        .nest("/fyhz", fyhz::build_app()) //费用汇总
+        .nest("/tj", tj::build_app()) //调价
+        .nest("/export", export::build_app()) //导出
+        .nest("/drqd", drqd::build_app()) //导入清单
+        .nest("/exchange", exchange::build_app()) //数据交换
+        .nest("/report", report::build_app()) //打印报表
+        .nest("/clipboard", clipboard::build_app()) //复制粘贴
+        .nest("/xmbm", xmbm::build_app()) //项目编码
+        .nest("/schema", schema::build_app()) //模型结构
 }
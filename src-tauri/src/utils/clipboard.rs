@@ -0,0 +1,77 @@
+use std::{collections::HashSet, sync::RwLock};
+
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::exchange::json_tree::{export_tree, JsonTreeNode};
+
+/// 剪贴板 在所有打开的工程项目间共享 支持跨工程项目粘贴
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clipboard {
+    /// 复制来源工程项目
+    pub editor_name: String,
+    /// 复制的节点 id
+    pub ids: Vec<NodeId>,
+    /// 剪切 粘贴后删除来源节点
+    pub cut: bool,
+    /// 复制时的子树快照
+    pub nodes: Vec<JsonTreeNode>,
+}
+
+lazy_static! {
+    static ref CLIPBOARD: RwLock<Option<Clipboard>> = RwLock::new(None);
+}
+
+pub fn set_clipboard(clipboard: Clipboard) {
+    *CLIPBOARD.write().unwrap() = Some(clipboard);
+}
+
+pub fn get_clipboard() -> Option<Clipboard> {
+    CLIPBOARD.read().unwrap().clone()
+}
+
+pub fn clear_clipboard() {
+    *CLIPBOARD.write().unwrap() = None;
+}
+
+/// 节点是否为 ancestor_id 本身或其下级
+pub fn is_self_or_descendant(doc: &NodePool, id: &NodeId, ancestor_id: &NodeId) -> bool {
+    let mut current = doc.get_node(id);
+    while let Some(node) = current {
+        if &node.id == ancestor_id {
+            return true;
+        }
+        current = doc.get_parent_node(&node.id);
+    }
+    false
+}
+
+/// 复制子树快照 已选中祖先节点的下级不重复复制
+pub fn copy_nodes(
+    doc: &NodePool,
+    ids: &[NodeId],
+) -> anyhow::Result<(Vec<NodeId>, Vec<JsonTreeNode>)> {
+    let selected: HashSet<&NodeId> = ids.iter().collect();
+    let mut roots = Vec::new();
+    let mut nodes = Vec::new();
+    for id in ids.iter() {
+        let parent = match doc.get_parent_node(id) {
+            Some(parent) => parent,
+            None => {
+                if doc.get_node(id).is_some() {
+                    return Err(anyhow::anyhow!("工程项目根节点不能复制".to_string()));
+                }
+                return Err(anyhow::anyhow!("节点 {} 不存在", id));
+            }
+        };
+        let covered = selected
+            .iter()
+            .any(|other| *other != id && is_self_or_descendant(doc, &parent.id, other));
+        if covered || roots.contains(id) {
+            continue;
+        }
+        roots.push(id.clone());
+        nodes.push(export_tree(doc, id)?.root);
+    }
+    Ok((roots, nodes))
+}
@@ -0,0 +1,125 @@
+/// 大写数字
+const DIGITS: [char; 10] = ['零', '壹', '贰', '叁', '肆', '伍', '陆', '柒', '捌', '玖'];
+/// 节内单位 个、拾、佰、仟
+const UNITS: [&str; 4] = ["", "拾", "佰", "仟"];
+/// 节单位 每 4 位一节
+const SECTION_UNITS: [&str; 5] = ["", "万", "亿", "万亿", "亿亿"];
+
+/// 4 位以内数字转大写 不含前导零
+fn section_upper(section: u64) -> String {
+    let mut result = String::new();
+    let mut zero = false;
+    for pos in (0..4).rev() {
+        let digit = (section / 10u64.pow(pos as u32) % 10) as usize;
+        if digit == 0 {
+            zero = !result.is_empty();
+            continue;
+        }
+        if zero {
+            result.push(DIGITS[0]);
+            zero = false;
+        }
+        result.push(DIGITS[digit]);
+        result.push_str(UNITS[pos]);
+    }
+    result
+}
+
+/// 整数部分转大写
+fn integer_upper(value: u64) -> String {
+    let mut sections = Vec::new();
+    let mut rest = value;
+    while rest > 0 {
+        sections.push(rest % 10000);
+        rest /= 10000;
+    }
+    let mut result = String::new();
+    let mut zero = false;
+    for (index, section) in sections.iter().enumerate().rev() {
+        if *section == 0 {
+            zero = !result.is_empty();
+            continue;
+        }
+        // 上一节有数字 本节不足千位或中间有整节为零时补 零
+        if !result.is_empty() && (zero || *section < 1000) {
+            result.push(DIGITS[0]);
+        }
+        result.push_str(&section_upper(*section));
+        result.push_str(SECTION_UNITS[index]);
+        zero = false;
+    }
+    result
+}
+
+/// 人民币金额大写 四舍五入到分
+/// 例如 1004.5 → 壹仟零肆元伍角整 0.05 → 伍分
+pub fn rmb_upper(amount: f64) -> String {
+    let cents = (amount.abs() * 100.0).round() as u64;
+    if cents == 0 {
+        return "零元整".to_string();
+    }
+    let yuan = cents / 100;
+    let jiao = (cents / 10 % 10) as usize;
+    let fen = (cents % 10) as usize;
+    let mut result = String::new();
+    if amount < 0.0 {
+        result.push('负');
+    }
+    if yuan > 0 {
+        result.push_str(&integer_upper(yuan));
+        result.push('元');
+    }
+    match (jiao, fen) {
+        (0, 0) => result.push('整'),
+        (0, _) => {
+            if yuan > 0 {
+                result.push(DIGITS[0]);
+            }
+            result.push(DIGITS[fen]);
+            result.push('分');
+        }
+        (_, 0) => {
+            result.push(DIGITS[jiao]);
+            result.push_str("角整");
+        }
+        _ => {
+            result.push(DIGITS[jiao]);
+            result.push('角');
+            result.push(DIGITS[fen]);
+            result.push('分');
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rmb_upper;
+
+    #[test]
+    fn zero_between_sections() {
+        assert_eq!(rmb_upper(10005.0), "壹万零伍元整");
+        assert_eq!(rmb_upper(100000005.0), "壹亿零伍元整");
+        assert_eq!(rmb_upper(100000.0), "壹拾万元整");
+    }
+
+    #[test]
+    fn zero_inside_section() {
+        assert_eq!(rmb_upper(1004.5), "壹仟零肆元伍角整");
+        assert_eq!(rmb_upper(1010.0), "壹仟零壹拾元整");
+    }
+
+    #[test]
+    fn jiao_and_fen() {
+        assert_eq!(rmb_upper(0.05), "伍分");
+        assert_eq!(rmb_upper(12.05), "壹拾贰元零伍分");
+        assert_eq!(rmb_upper(0.56), "伍角陆分");
+        assert_eq!(rmb_upper(0.0), "零元整");
+    }
+
+    #[test]
+    fn negative_amount() {
+        assert_eq!(rmb_upper(-1004.5), "负壹仟零肆元伍角整");
+        assert_eq!(rmb_upper(-0.05), "负伍分");
+    }
+}
@@ -0,0 +1,347 @@
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    nodes::{
+        fbfx_csxm::{CSXM_STR, DE_STR, FBFX_STR, QD_STR},
+        gcxm::{DWGC_STR, DXGC_STR, GCXM_STR, JGCL_MODE_DEDUCT, JGCL_MODE_EXCLUDE},
+        rcj::RCJ_STR,
+    },
+    utils::{
+        money::{get_decimal, Decimal},
+        node::{children, children_of_type, descendants_of_type, get_str},
+        price::{
+            is_jgcl, is_locked, is_zgcl, locked_ancestor, qd_amount, rcj_jc, rcj_price,
+            PriceOptions,
+        },
+    },
+};
+
+/// 费用汇总行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FyhzRow {
+    /// 费用代号
+    pub code: String,
+    pub name: String,
+    /// 计算基数说明
+    pub base: String,
+    pub amount: Decimal,
+    /// 是否计入工程造价 仅展示的行为 false
+    pub in_total: bool,
+}
+
+/// 单位工程费用汇总 金额按单位工程的合价小数位数取整
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fyhz {
+    pub id: NodeId,
+    pub name: String,
+    pub rows: Vec<FyhzRow>,
+    /// 分部分项工程费
+    pub fbfx: Decimal,
+    /// 措施项目费
+    pub csxm: Decimal,
+    /// 其他项目费
+    pub qtxm: Decimal,
+    /// 规费
+    pub gf: Decimal,
+    /// 税金
+    pub sj: Decimal,
+    /// 工程造价
+    pub total: Decimal,
+}
+
+/// 工程项目汇总表 各级节点的费用构成
+/// 单位工程取费用汇总 单项工程、工程项目为下属单项、单位工程小计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct XmhzItem {
+    pub id: NodeId,
+    pub r#type: String,
+    pub code: String,
+    pub name: String,
+    /// 层级 汇总起点为 0
+    pub level: usize,
+    /// 分部分项工程费
+    pub fbfx: Decimal,
+    /// 措施项目费
+    pub csxm: Decimal,
+    /// 其他项目费
+    pub qtxm: Decimal,
+    /// 规费
+    pub gf: Decimal,
+    /// 税金
+    pub sj: Decimal,
+    /// 合计
+    pub total: Decimal,
+    /// 占汇总起点合计的百分比
+    pub share: Decimal,
+    pub children: Vec<XmhzItem>,
+}
+
+impl XmhzItem {
+    /// 从 工程项目、单项工程 或 单位工程 向下汇总
+    pub fn build(doc: &NodePool, id: &NodeId) -> Option<Self> {
+        let mut item = Self::collect(doc, id, 0)?;
+        let total = item.total;
+        item.set_share(total);
+        Some(item)
+    }
+
+    fn collect(doc: &NodePool, id: &NodeId, level: usize) -> Option<Self> {
+        let node = doc.get_node(id)?;
+        let mut item = Self {
+            id: node.id.clone(),
+            r#type: node.r#type.to_string(),
+            code: get_str(&node, "code"),
+            name: get_str(&node, "name"),
+            level,
+            ..Default::default()
+        };
+        if node.r#type == DWGC_STR {
+            if let Some(fyhz) = Fyhz::build(doc, id) {
+                item.fbfx = fyhz.fbfx;
+                item.csxm = fyhz.csxm;
+                item.qtxm = fyhz.qtxm;
+                item.gf = fyhz.gf;
+                item.sj = fyhz.sj;
+                item.total = fyhz.total;
+            }
+            return Some(item);
+        }
+        if node.r#type != GCXM_STR && node.r#type != DXGC_STR {
+            return None;
+        }
+        for child in children(doc, id) {
+            if let Some(child) = Self::collect(doc, &child.id, level + 1) {
+                item.fbfx += child.fbfx;
+                item.csxm += child.csxm;
+                item.qtxm += child.qtxm;
+                item.gf += child.gf;
+                item.sj += child.sj;
+                item.total += child.total;
+                item.children.push(child);
+            }
+        }
+        Some(item)
+    }
+
+    fn set_share(&mut self, total: Decimal) {
+        self.share = if total.is_zero() {
+            Decimal::ZERO
+        } else {
+            (self.total / total * Decimal::ONE_HUNDRED).round_dp(2)
+        };
+        for child in self.children.iter_mut() {
+            child.set_share(total);
+        }
+    }
+
+    /// 按先序展开为行 用于报表、导出
+    pub fn flatten(&self) -> Vec<XmhzItem> {
+        let mut rows = vec![XmhzItem {
+            children: vec![],
+            ..self.clone()
+        }];
+        for child in self.children.iter() {
+            rows.extend(child.flatten());
+        }
+        rows
+    }
+}
+
+/// 节点下所有清单的 合价 合计
+pub fn qd_total(doc: &NodePool, id: &NodeId, options: &PriceOptions) -> Decimal {
+    descendants_of_type(doc, id, &[QD_STR])
+        .iter()
+        .map(|qd| qd_amount(doc, qd, options).total)
+        .sum()
+}
+
+/// 节点下满足条件的人材机合价 = Σ 消耗量 × 定额工程量 × 单价
+pub fn rcj_total<F>(doc: &NodePool, id: &NodeId, filter: F) -> Decimal
+where
+    F: Fn(&Node) -> bool,
+{
+    descendants_of_type(doc, id, &[DE_STR])
+        .iter()
+        .map(|de| {
+            let quantity = get_decimal(de, "quantity");
+            children_of_type(doc, &de.id, RCJ_STR)
+                .iter()
+                .filter(|rcj| filter(rcj))
+                .map(|rcj| get_decimal(rcj, "resQty") * rcj_price(rcj) * quantity)
+                .sum::<Decimal>()
+        })
+        .sum()
+}
+
+/// 节点下人材机价差合计 = Σ 消耗量 × 定额工程量 × (市场价 - 定额基价)
+/// 与综合单价口径一致 不计入单价的甲供材料不计价差 已锁定的清单、定额使用锁定单价 不计价差
+pub fn jc_total(doc: &NodePool, id: &NodeId, options: &PriceOptions) -> Decimal {
+    descendants_of_type(doc, id, &[DE_STR])
+        .iter()
+        .filter(|de| !is_locked(de) && locked_ancestor(doc, &de.id).is_none())
+        .map(|de| {
+            let quantity = get_decimal(de, "quantity");
+            children_of_type(doc, &de.id, RCJ_STR)
+                .iter()
+                .filter(|rcj| options.jgcl_in_price() || !is_jgcl(rcj))
+                .map(|rcj| get_decimal(rcj, "resQty") * rcj_jc(rcj) * quantity)
+                .sum::<Decimal>()
+        })
+        .sum()
+}
+
+/// 节点的工程造价 单位工程取费用汇总合计 工程项目、单项工程为下属单位工程合计
+pub fn gczj_total(doc: &NodePool, id: &NodeId) -> Decimal {
+    match doc.get_node(id) {
+        Some(node) if node.r#type == DWGC_STR => Fyhz::build(doc, id)
+            .map(|fyhz| fyhz.total)
+            .unwrap_or_default(),
+        Some(_) => descendants_of_type(doc, id, &[DWGC_STR])
+            .iter()
+            .filter_map(|dwgc| Fyhz::build(doc, &dwgc.id))
+            .map(|fyhz| fyhz.total)
+            .sum(),
+        None => Decimal::ZERO,
+    }
+}
+
+impl Fyhz {
+    /// 计算单位工程费用汇总
+    pub fn build(doc: &NodePool, dwgc_id: &NodeId) -> Option<Self> {
+        let dwgc = doc.get_node(dwgc_id)?;
+        if dwgc.r#type != DWGC_STR {
+            return None;
+        }
+        let options = PriceOptions::of(doc, dwgc_id);
+        let rounding = &options.rounding;
+        let fbfx_total: Decimal = children_of_type(doc, dwgc_id, FBFX_STR)
+            .iter()
+            .map(|n| qd_total(doc, &n.id, &options))
+            .sum();
+        let csxm_total: Decimal = children_of_type(doc, dwgc_id, CSXM_STR)
+            .iter()
+            .map(|n| qd_total(doc, &n.id, &options))
+            .sum();
+        let qtxm_total = rounding.total(get_decimal(&dwgc, "qtxmTotal"));
+        let zgcl_total = rounding.total(rcj_total(doc, dwgc_id, is_zgcl));
+        let jgcl_total = rounding.total(rcj_total(doc, dwgc_id, is_jgcl));
+        let gf_rate = get_decimal(&dwgc, "gfRate");
+        let sj_rate = get_decimal(&dwgc, "sjRate");
+        let gf =
+            rounding.total((fbfx_total + csxm_total + qtxm_total) * gf_rate / Decimal::ONE_HUNDRED);
+
+        let mut rows = vec![
+            FyhzRow {
+                code: "FBFXHJ".to_string(),
+                name: "分部分项工程费".to_string(),
+                base: "分部分项合计".to_string(),
+                amount: fbfx_total,
+                in_total: true,
+            },
+            FyhzRow {
+                code: "CSXMHJ".to_string(),
+                name: "措施项目费".to_string(),
+                base: "措施项目合计".to_string(),
+                amount: csxm_total,
+                in_total: true,
+            },
+            FyhzRow {
+                code: "QTXMHJ".to_string(),
+                name: "其他项目费".to_string(),
+                base: "其他项目合计".to_string(),
+                amount: qtxm_total,
+                in_total: true,
+            },
+            FyhzRow {
+                code: "GF".to_string(),
+                name: "规费".to_string(),
+                base: format!("(FBFXHJ+CSXMHJ+QTXMHJ)×{}%", gf_rate),
+                amount: gf,
+                in_total: true,
+            },
+            FyhzRow {
+                code: "ZGCLF".to_string(),
+                name: "其中：暂估材料费".to_string(),
+                base: "暂估材料合计".to_string(),
+                amount: zgcl_total,
+                in_total: false,
+            },
+        ];
+        let mut total = fbfx_total + csxm_total + qtxm_total + gf;
+        let mut total_base = "FBFXHJ+CSXMHJ+QTXMHJ+GF".to_string();
+        if options.jgcl_mode == JGCL_MODE_DEDUCT {
+            total_base.push_str("+JGCLF");
+            total -= jgcl_total;
+            rows.push(FyhzRow {
+                code: "JGCLF".to_string(),
+                name: "扣除甲供材料费".to_string(),
+                base: "甲供材料合计".to_string(),
+                amount: -jgcl_total,
+                in_total: true,
+            });
+        } else {
+            let name = if options.jgcl_mode == JGCL_MODE_EXCLUDE {
+                "甲供材料费(不计入)"
+            } else {
+                "其中：甲供材料费"
+            };
+            rows.push(FyhzRow {
+                code: "JGCLF".to_string(),
+                name: name.to_string(),
+                base: "甲供材料合计".to_string(),
+                amount: jgcl_total,
+                in_total: false,
+            });
+        }
+        let jc = rounding.total(jc_total(doc, dwgc_id, &options));
+        if options.jc_separate() {
+            total_base.push_str("+JC");
+            total += jc;
+            rows.push(FyhzRow {
+                code: "JC".to_string(),
+                name: "价差".to_string(),
+                base: "人材机价差合计".to_string(),
+                amount: jc,
+                in_total: true,
+            });
+        } else {
+            rows.push(FyhzRow {
+                code: "JC".to_string(),
+                name: "其中：价差".to_string(),
+                base: "人材机价差合计".to_string(),
+                amount: jc,
+                in_total: false,
+            });
+        }
+        // 税金 以税前工程造价为基数
+        let sj = rounding.total(total * sj_rate / Decimal::ONE_HUNDRED);
+        rows.push(FyhzRow {
+            code: "SJ".to_string(),
+            name: "税金".to_string(),
+            base: format!("税前工程造价×{}%", sj_rate),
+            amount: sj,
+            in_total: true,
+        });
+        total_base.push_str("+SJ");
+        total += sj;
+        rows.push(FyhzRow {
+            code: "GCZJ".to_string(),
+            name: "工程造价".to_string(),
+            base: total_base,
+            amount: total,
+            in_total: false,
+        });
+        Some(Fyhz {
+            id: dwgc.id.clone(),
+            name: get_str(&dwgc, "name"),
+            rows,
+            fbfx: fbfx_total,
+            csxm: csxm_total,
+            qtxm: qtxm_total,
+            gf,
+            sj,
+            total,
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fs, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// 本地库根目录
+/// Windows 下为 %APPDATA%/moduforge-demo 其他平台为 $HOME/.moduforge-demo
+/// 可通过环境变量 MODUFORGE_DATA_DIR 覆盖
+pub fn local_data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("MODUFORGE_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    if let Ok(dir) = std::env::var("APPDATA") {
+        return PathBuf::from(dir).join("moduforge-demo");
+    }
+    if let Ok(dir) = std::env::var("HOME") {
+        return PathBuf::from(dir).join(".moduforge-demo");
+    }
+    PathBuf::from(".moduforge-demo")
+}
+
+/// 用户本地库文件路径 library/{name}.json
+pub fn library_path(name: &str) -> PathBuf {
+    local_data_dir()
+        .join("library")
+        .join(format!("{}.json", name))
+}
+
+/// 读取本地库 文件不存在时返回默认值
+pub fn load_library<T: DeserializeOwned + Default>(name: &str) -> anyhow::Result<T> {
+    let path = library_path(name);
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("本地库 {} 解析失败: {}", path.display(), e))
+}
+
+/// 保存本地库 先写临时文件再替换 避免写入中断损坏库文件
+pub fn save_library<T: Serialize>(name: &str, value: &T) -> anyhow::Result<()> {
+    let path = library_path(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
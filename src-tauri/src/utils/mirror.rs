@@ -0,0 +1,436 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use mf_model::{mark::Mark, node::Node, node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    plugins::inc::{IncEntry, IncFeed, Operation},
+    utils::node::attrs_map,
+};
+
+/// 镜像节点
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorNode {
+    pub id: NodeId,
+    pub r#type: String,
+    pub attrs: BTreeMap<String, Value>,
+    pub marks: Vec<Mark>,
+    pub content: Vec<NodeId>,
+}
+
+impl From<&Node> for MirrorNode {
+    fn from(node: &Node) -> Self {
+        Self {
+            id: node.id.clone(),
+            r#type: node.r#type.to_string(),
+            attrs: attrs_map(node).into_iter().collect(),
+            marks: node.marks.iter().cloned().collect(),
+            content: node.content.iter().cloned().collect(),
+        }
+    }
+}
+
+/// 镜像与源文档的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorIssue {
+    pub id: NodeId,
+    pub message: String,
+}
+
+/// 只读镜像文档 增量数据协议的参考实现
+/// 以某一版本的文档快照初始化 按版本顺序应用 IncStateField 产生的增量数据
+/// NodePool 不可变 镜像使用独立的可变结构保存
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Mirror {
+    pub root_id: NodeId,
+    /// 已应用的增量数据版本
+    pub version: u64,
+    nodes: HashMap<NodeId, MirrorNode>,
+    parents: HashMap<NodeId, NodeId>,
+}
+
+impl Mirror {
+    /// 从文档快照创建 version 为快照对应的增量数据版本
+    pub fn from_doc(doc: &NodePool, root_id: &NodeId, version: u64) -> Self {
+        let mut mirror = Self {
+            root_id: root_id.clone(),
+            version,
+            ..Default::default()
+        };
+        let mut stack = vec![root_id.clone()];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = doc.get_node(&id) {
+                for child_id in node.content.iter() {
+                    mirror.parents.insert(child_id.clone(), id.clone());
+                    stack.push(child_id.clone());
+                }
+                mirror
+                    .nodes
+                    .insert(id.clone(), MirrorNode::from(node.as_ref()));
+            }
+        }
+        mirror
+    }
+
+    pub fn get_node(&self, id: &NodeId) -> Option<&MirrorNode> {
+        self.nodes.get(id)
+    }
+
+    pub fn get_parent_id(&self, id: &NodeId) -> Option<&NodeId> {
+        self.parents.get(id)
+    }
+
+    /// 按文档顺序获取直接子节点
+    pub fn children(&self, id: &NodeId) -> Vec<&MirrorNode> {
+        match self.nodes.get(id) {
+            Some(node) => node
+                .content
+                .iter()
+                .filter_map(|child_id| self.nodes.get(child_id))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// 应用增量数据查询结果 需要全量同步时返回错误 调用方应重新获取快照
+    pub fn apply_feed(&mut self, feed: &IncFeed) -> anyhow::Result<()> {
+        if feed.resync_required {
+            return Err(anyhow::anyhow!(
+                "增量数据已超出保留范围 需要重新获取全量数据"
+            ));
+        }
+        for entry in feed.entries.iter() {
+            self.apply_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// 应用一个事务的增量数据 已应用的版本忽略 版本不连续时返回错误
+    pub fn apply_entry(&mut self, entry: &IncEntry) -> anyhow::Result<()> {
+        if entry.version <= self.version {
+            return Ok(());
+        }
+        if entry.version != self.version + 1 {
+            return Err(anyhow::anyhow!(
+                "增量数据版本不连续 当前 {} 收到 {}",
+                self.version,
+                entry.version
+            ));
+        }
+        self.apply(&entry.operations)?;
+        self.version = entry.version;
+        Ok(())
+    }
+
+    /// 按顺序应用操作
+    pub fn apply(&mut self, operations: &[Operation]) -> anyhow::Result<()> {
+        for operation in operations.iter() {
+            self.apply_operation(operation)?;
+        }
+        Ok(())
+    }
+
+    fn apply_operation(&mut self, operation: &Operation) -> anyhow::Result<()> {
+        match operation {
+            Operation::UpdateNode(parent_id, nodes) => {
+                if !self.nodes.contains_key(parent_id) {
+                    return Err(anyhow::anyhow!("父节点 {} 不存在", parent_id));
+                }
+                // 不被其他新增节点包含的为本次新增的顶层节点
+                let nested: HashSet<&NodeId> =
+                    nodes.iter().flat_map(|node| node.content.iter()).collect();
+                for node in nodes.iter() {
+                    for child_id in node.content.iter() {
+                        self.parents.insert(child_id.clone(), node.id.clone());
+                    }
+                    self.nodes
+                        .insert(node.id.clone(), MirrorNode::from(node.as_ref()));
+                }
+                for node in nodes.iter() {
+                    if nested.contains(&node.id) {
+                        continue;
+                    }
+                    self.detach(&node.id);
+                    self.parents.insert(node.id.clone(), parent_id.clone());
+                    if let Some(parent) = self.nodes.get_mut(parent_id) {
+                        parent.content.push(node.id.clone());
+                    }
+                }
+            }
+            Operation::RemoveNode(ids) => {
+                for id in ids.iter() {
+                    self.detach(id);
+                    self.nodes.remove(id);
+                }
+            }
+            Operation::UpdateAttrs(id, changes) => {
+                let node = self
+                    .nodes
+                    .get_mut(id)
+                    .ok_or_else(|| anyhow::anyhow!("节点 {} 不存在", id))?;
+                for (key, change) in changes.iter() {
+                    if change.new.is_null() {
+                        node.attrs.remove(key);
+                    } else {
+                        node.attrs.insert(key.clone(), change.new.clone());
+                    }
+                }
+            }
+            Operation::AddMark(id, marks) => {
+                let node = self
+                    .nodes
+                    .get_mut(id)
+                    .ok_or_else(|| anyhow::anyhow!("节点 {} 不存在", id))?;
+                for mark in marks.iter() {
+                    node.marks.retain(|item| item.r#type != mark.r#type);
+                    node.marks.push(mark.clone());
+                }
+            }
+            Operation::RemoveMark(id, mark_types) => {
+                let node = self
+                    .nodes
+                    .get_mut(id)
+                    .ok_or_else(|| anyhow::anyhow!("节点 {} 不存在", id))?;
+                node.marks
+                    .retain(|mark| !mark_types.contains(&mark.r#type.to_string()));
+            }
+            Operation::MoveNode(id, parent_id, index) => {
+                if !self.nodes.contains_key(id) {
+                    return Err(anyhow::anyhow!("节点 {} 不存在", id));
+                }
+                self.detach(id);
+                let parent = self
+                    .nodes
+                    .get_mut(parent_id)
+                    .ok_or_else(|| anyhow::anyhow!("父节点 {} 不存在", parent_id))?;
+                match index {
+                    Some(index) if *index > parent.content.len() => {
+                        return Err(anyhow::anyhow!(
+                            "节点 {} 的目标位置 {} 超出父节点 {} 的子节点数 {}",
+                            id,
+                            index,
+                            parent_id,
+                            parent.content.len()
+                        ));
+                    }
+                    Some(index) => parent.content.insert(*index, id.clone()),
+                    None => parent.content.push(id.clone()),
+                }
+                self.parents.insert(id.clone(), parent_id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// 从父节点中移除
+    fn detach(&mut self, id: &NodeId) {
+        if let Some(parent_id) = self.parents.remove(id) {
+            if let Some(parent) = self.nodes.get_mut(&parent_id) {
+                parent.content.retain(|child_id| child_id != id);
+            }
+        }
+    }
+
+    /// 与源文档比较 返回不一致的节点 为空表示镜像与源文档一致
+    pub fn check(&self, doc: &NodePool) -> Vec<MirrorIssue> {
+        let mut issues = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![self.root_id.clone()];
+        while let Some(id) = stack.pop() {
+            let node = match doc.get_node(&id) {
+                Some(node) => node,
+                None => {
+                    issues.push(MirrorIssue {
+                        id,
+                        message: "源文档中不存在".to_string(),
+                    });
+                    continue;
+                }
+            };
+            visited.insert(id.clone());
+            stack.extend(node.content.iter().cloned());
+            let expected = MirrorNode::from(node.as_ref());
+            let actual = match self.nodes.get(&id) {
+                Some(actual) => actual,
+                None => {
+                    issues.push(MirrorIssue {
+                        id,
+                        message: "镜像中缺少节点".to_string(),
+                    });
+                    continue;
+                }
+            };
+            if actual.r#type != expected.r#type {
+                issues.push(MirrorIssue {
+                    id: id.clone(),
+                    message: format!("类型不一致 {} / {}", actual.r#type, expected.r#type),
+                });
+            }
+            for (key, value) in expected.attrs.iter() {
+                let mirror_value = actual.attrs.get(key).unwrap_or(&Value::Null);
+                if mirror_value != value {
+                    issues.push(MirrorIssue {
+                        id: id.clone(),
+                        message: format!("属性 {} 不一致 {} / {}", key, mirror_value, value),
+                    });
+                }
+            }
+            for key in actual.attrs.keys() {
+                if !expected.attrs.contains_key(key) {
+                    issues.push(MirrorIssue {
+                        id: id.clone(),
+                        message: format!("多余属性 {}", key),
+                    });
+                }
+            }
+            if serde_json::to_value(&actual.marks).ok()
+                != serde_json::to_value(&expected.marks).ok()
+            {
+                issues.push(MirrorIssue {
+                    id: id.clone(),
+                    message: "标记不一致".to_string(),
+                });
+            }
+            if actual.content != expected.content {
+                issues.push(MirrorIssue {
+                    id: id.clone(),
+                    message: "子节点或顺序不一致".to_string(),
+                });
+            }
+        }
+        for id in self.nodes.keys() {
+            if !visited.contains(id) && doc.get_node(id).is_none() {
+                issues.push(MirrorIssue {
+                    id: id.clone(),
+                    message: "镜像中多余的节点".to_string(),
+                });
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use mf_model::{node::Node, node_pool::NodePool, node_type::NodeEnum};
+    use serde_json::{json, Value};
+
+    use super::Mirror;
+    use crate::plugins::inc::{AttrChange, Operation};
+
+    fn node(id: &str, attrs: Value, content: &[&str]) -> Node {
+        Node::new(
+            id,
+            "FB".to_string(),
+            serde_json::from_value(attrs).unwrap(),
+            content.iter().map(|id| id.to_string()).collect(),
+            vec![],
+        )
+    }
+
+    fn leaf(id: &str, attrs: Value) -> NodeEnum {
+        NodeEnum(node(id, attrs, &[]), vec![])
+    }
+
+    /// root 下依次为 a b c
+    fn doc() -> NodePool {
+        NodePool::from(NodeEnum(
+            node("root", json!({}), &["a", "b", "c"]),
+            vec![
+                leaf("a", json!({ "name": "a" })),
+                leaf("b", json!({ "name": "b" })),
+                leaf("c", json!({ "name": "c" })),
+            ],
+        ))
+        .as_ref()
+        .clone()
+    }
+
+    fn mirror() -> Mirror {
+        Mirror::from_doc(&doc(), &"root".to_string(), 0)
+    }
+
+    fn attr_change(old: &str, new: &str) -> BTreeMap<String, AttrChange> {
+        BTreeMap::from([(
+            "name".to_string(),
+            AttrChange {
+                old: json!(old),
+                new: json!(new),
+            },
+        )])
+    }
+
+    #[test]
+    fn replay_add_attr_move_remove() {
+        let mut mirror = mirror();
+        mirror
+            .apply(&[
+                Operation::UpdateNode(
+                    "root".to_string(),
+                    vec![
+                        Arc::new(node("d", json!({ "name": "d" }), &["e"])),
+                        Arc::new(node("e", json!({ "name": "e" }), &[])),
+                    ],
+                ),
+                Operation::UpdateAttrs("a".to_string(), attr_change("a", "a1")),
+                Operation::MoveNode("c".to_string(), "root".to_string(), Some(0)),
+                Operation::RemoveNode(vec!["b".to_string()]),
+            ])
+            .unwrap();
+        let expected = NodePool::from(NodeEnum(
+            node("root", json!({}), &["c", "a", "d"]),
+            vec![
+                leaf("c", json!({ "name": "c" })),
+                leaf("a", json!({ "name": "a1" })),
+                NodeEnum(
+                    node("d", json!({ "name": "d" }), &["e"]),
+                    vec![leaf("e", json!({ "name": "e" }))],
+                ),
+            ],
+        ));
+        assert!(mirror.check(&expected).is_empty());
+    }
+
+    #[test]
+    fn replay_moves_under_same_parent() {
+        let mut mirror = mirror();
+        mirror
+            .apply(&[
+                Operation::MoveNode("c".to_string(), "root".to_string(), Some(0)),
+                Operation::MoveNode("a".to_string(), "root".to_string(), Some(2)),
+                Operation::MoveNode("b".to_string(), "root".to_string(), None),
+            ])
+            .unwrap();
+        let expected = NodePool::from(NodeEnum(
+            node("root", json!({}), &["c", "a", "b"]),
+            vec![
+                leaf("c", json!({ "name": "c" })),
+                leaf("a", json!({ "name": "a" })),
+                leaf("b", json!({ "name": "b" })),
+            ],
+        ));
+        assert!(mirror.check(&expected).is_empty());
+    }
+
+    #[test]
+    fn move_out_of_range() {
+        let mut mirror = mirror();
+        let result = mirror.apply(&[Operation::MoveNode(
+            "a".to_string(),
+            "root".to_string(),
+            Some(3),
+        )]);
+        assert!(result.is_err());
+    }
+}
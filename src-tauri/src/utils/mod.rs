@@ -1 +1,10 @@
+pub mod clipboard;
+pub mod dxje;
+pub mod fyhz;
+pub mod local_library;
+pub mod mirror;
+pub mod money;
 pub mod node;
+pub mod price;
+pub mod push;
+pub mod xmbm;
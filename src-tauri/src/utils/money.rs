@@ -0,0 +1,221 @@
+use std::{collections::HashMap, str::FromStr};
+
+use mf_model::node::Node;
+pub use rust_decimal::Decimal;
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    RoundingStrategy,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    nodes::gcxm::{ROUND_MODE_MULTIPLY, ROUND_MODE_ROUND},
+    utils::node::get_str,
+};
+
+/// 中间计算结果保留的小数位数 由浮点数(调价系数等)换算时使用
+pub const CALC_PRECISION: u32 = 8;
+
+/// 解析数值 兼容数字与字符串存储("12.50") 无法解析时返回 None
+pub fn parse_decimal(value: &Value) -> Option<Decimal> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        Value::Bool(b) => return Some(if *b { Decimal::ONE } else { Decimal::ZERO }),
+        _ => return None,
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+}
+
+/// 读取节点数值属性 无法解析时返回 0
+pub fn get_decimal(node: &Node, key: &str) -> Decimal {
+    node.attrs
+        .get_value::<Value>(key)
+        .and_then(|value| parse_decimal(&value))
+        .unwrap_or_default()
+}
+
+/// 存储格式 以字符串保存精确值 如 "12.50"
+pub fn decimal_value(value: Decimal) -> Value {
+    Value::String(value.to_string())
+}
+
+pub fn from_f64(value: f64) -> Decimal {
+    Decimal::from_f64(value)
+        .map(|value| round(value, CALC_PRECISION).normalize())
+        .unwrap_or_default()
+}
+
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+/// 四舍五入并补足小数位数 12.5 保留两位为 12.50
+pub fn round(value: Decimal, precision: u32) -> Decimal {
+    let mut value = value.round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero);
+    value.rescale(precision);
+    value
+}
+
+/// 单位工程取整规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rounding {
+    /// 工程量小数位数
+    pub qty_precision: u32,
+    /// 按计量单位设置的工程量小数位数 如 t 保留 3 位、个 保留 0 位
+    pub unit_qty_precision: HashMap<String, u32>,
+    /// 单价小数位数
+    pub price_precision: u32,
+    /// 合价小数位数
+    pub total_precision: u32,
+    /// 取整方式 round/multiply
+    pub mode: String,
+}
+
+impl Default for Rounding {
+    fn default() -> Self {
+        Self {
+            qty_precision: 3,
+            unit_qty_precision: HashMap::new(),
+            price_precision: 2,
+            total_precision: 2,
+            mode: ROUND_MODE_ROUND.to_string(),
+        }
+    }
+}
+
+impl Rounding {
+    /// 读取单位工程的取整设置 未设置的项使用默认值
+    pub fn from_dwgc(dwgc: &Node) -> Self {
+        let mut rounding = Self::default();
+        let precision = |key: &str| {
+            dwgc.attrs
+                .get_value::<Value>(key)
+                .and_then(|value| parse_decimal(&value))
+                .and_then(|value| value.to_u32())
+        };
+        if let Some(value) = precision("qtyPrecision") {
+            rounding.qty_precision = value;
+        }
+        if let Some(value) = precision("pricePrecision") {
+            rounding.price_precision = value;
+        }
+        if let Some(value) = precision("totalPrecision") {
+            rounding.total_precision = value;
+        }
+        if let Some(Value::Object(map)) = dwgc.attrs.get_value::<Value>("unitQtyPrecision") {
+            for (unit, value) in map.iter() {
+                if let Some(value) = parse_decimal(value).and_then(|value| value.to_u32()) {
+                    rounding
+                        .unit_qty_precision
+                        .insert(unit.trim().to_string(), value);
+                }
+            }
+        }
+        let mode = get_str(dwgc, "roundMode");
+        if mode == ROUND_MODE_ROUND || mode == ROUND_MODE_MULTIPLY {
+            rounding.mode = mode;
+        }
+        rounding
+    }
+
+    /// 单价、工程量是否先取整再相乘
+    pub fn round_first(&self) -> bool {
+        self.mode != ROUND_MODE_MULTIPLY
+    }
+
+    pub fn quantity(&self, unit: &str, value: Decimal) -> Decimal {
+        let precision = self
+            .unit_qty_precision
+            .get(unit.trim())
+            .copied()
+            .unwrap_or(self.qty_precision);
+        round(value, precision)
+    }
+
+    pub fn price(&self, value: Decimal) -> Decimal {
+        round(value, self.price_precision)
+    }
+
+    pub fn total(&self, value: Decimal) -> Decimal {
+        round(value, self.total_precision)
+    }
+
+    /// 合价 = 单价 × 工程量
+    /// round: 单价、工程量分别取整后相乘 multiply: 相乘后再取整
+    pub fn amount(&self, price: Decimal, quantity: Decimal, unit: &str) -> Decimal {
+        if self.round_first() {
+            self.total(self.price(price) * self.quantity(unit, quantity))
+        } else {
+            self.total(price * quantity)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, str::FromStr};
+
+    use super::{round, Decimal, Rounding};
+    use crate::nodes::gcxm::ROUND_MODE_MULTIPLY;
+
+    fn d(text: &str) -> Decimal {
+        Decimal::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn round_half_away_from_zero_and_pad() {
+        assert_eq!(round(d("12.5"), 2).to_string(), "12.50");
+        assert_eq!(round(d("2.345"), 2).to_string(), "2.35");
+        assert_eq!(round(d("-2.345"), 2).to_string(), "-2.35");
+        assert_eq!(round(d("2.5"), 0).to_string(), "3");
+    }
+
+    #[test]
+    fn amount_round_vs_multiply() {
+        let round_first = Rounding::default();
+        let multiply = Rounding {
+            mode: ROUND_MODE_MULTIPLY.to_string(),
+            ..Default::default()
+        };
+        // 单价 12.35 × 工程量 1.001 = 12.36235
+        assert_eq!(
+            round_first
+                .amount(d("12.345"), d("1.0005"), "m")
+                .to_string(),
+            "12.36"
+        );
+        // 12.345 × 1.0005 = 12.3511725
+        assert_eq!(
+            multiply.amount(d("12.345"), d("1.0005"), "m").to_string(),
+            "12.35"
+        );
+    }
+
+    #[test]
+    fn amount_unit_precision() {
+        let rounding = Rounding {
+            unit_qty_precision: HashMap::from([("个".to_string(), 0)]),
+            ..Default::default()
+        };
+        assert_eq!(rounding.quantity("个", d("2.5")).to_string(), "3");
+        assert_eq!(rounding.quantity(" 个 ", d("2.4")).to_string(), "2");
+        assert_eq!(rounding.quantity("m", d("2.5")).to_string(), "2.500");
+        assert_eq!(
+            rounding.amount(d("10"), d("2.4"), "个").to_string(),
+            "20.00"
+        );
+        assert_eq!(rounding.amount(d("10"), d("2.4"), "m").to_string(), "24.00");
+        let multiply = Rounding {
+            mode: ROUND_MODE_MULTIPLY.to_string(),
+            ..rounding
+        };
+        assert_eq!(
+            multiply.amount(d("10"), d("2.4"), "个").to_string(),
+            "24.00"
+        );
+    }
+}
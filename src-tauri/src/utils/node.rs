@@ -1 +1,75 @@
+use std::{collections::HashMap, sync::Arc};
 
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde_json::Value;
+
+/// 读取节点字符串属性 不存在时返回空字符串
+pub fn get_str(node: &Node, key: &str) -> String {
+    match node.attrs.get_value::<Value>(key) {
+        Some(Value::String(s)) => s,
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// 读取节点布尔属性 兼容 0/1 的旧数据
+pub fn get_bool(node: &Node, key: &str) -> bool {
+    match node.attrs.get_value::<Value>(key) {
+        Some(Value::Bool(b)) => b,
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(0.0) != 0.0,
+        Some(Value::String(s)) => s == "1" || s.eq_ignore_ascii_case("true"),
+        _ => false,
+    }
+}
+
+/// 读取节点全部属性
+pub fn attrs_map(node: &Node) -> HashMap<String, Value> {
+    match serde_json::to_value(&node.attrs) {
+        Ok(Value::Object(map)) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// 按文档顺序获取直接子节点
+pub fn children(doc: &NodePool, id: &NodeId) -> Vec<Arc<Node>> {
+    match doc.get_node(id) {
+        Some(node) => node
+            .content
+            .iter()
+            .filter_map(|child_id| doc.get_node(child_id))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// 按文档顺序获取指定类型的直接子节点
+pub fn children_of_type(doc: &NodePool, id: &NodeId, r#type: &str) -> Vec<Arc<Node>> {
+    children(doc, id)
+        .into_iter()
+        .filter(|n| n.r#type == r#type)
+        .collect()
+}
+
+/// 按文档顺序(先序遍历)获取指定类型的后代节点
+pub fn descendants_of_type(doc: &NodePool, id: &NodeId, types: &[&str]) -> Vec<Arc<Node>> {
+    let mut result = Vec::new();
+    for child in children(doc, id) {
+        if types.contains(&child.r#type.as_str()) {
+            result.push(child.clone());
+        }
+        result.extend(descendants_of_type(doc, &child.id, types));
+    }
+    result
+}
+
+/// 向上查找最近的指定类型祖先节点
+pub fn find_ancestor(doc: &NodePool, id: &NodeId, r#type: &str) -> Option<Arc<Node>> {
+    let mut current = doc.get_parent_node(id);
+    while let Some(node) = current {
+        if node.r#type == r#type {
+            return Some(node);
+        }
+        current = doc.get_parent_node(&node.id);
+    }
+    None
+}
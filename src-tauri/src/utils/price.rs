@@ -0,0 +1,428 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    nodes::{
+        djgc::{DJGC_ROW_STR, DJGC_STR, DJGC_TYPE_GLF, DJGC_TYPE_LR},
+        fbfx_csxm::{DE_STR, QD_STR},
+        gcxm::{DWGC_STR, JC_MODE_PRICE, JC_MODE_SEPARATE, JGCL_MODE_EXCLUDE, JGCL_MODE_INCLUDE},
+        rcj::RCJ_STR,
+    },
+    utils::{
+        money::{get_decimal, Decimal, Rounding},
+        node::{children_of_type, find_ancestor, get_bool, get_str},
+    },
+};
+
+/// 人材机分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RcjKind {
+    /// 人工
+    Rg,
+    /// 材料
+    Cl,
+    /// 机械
+    Jx,
+    /// 设备
+    Sb,
+    /// 主材
+    Zc,
+}
+
+impl RcjKind {
+    /// 根据 rcj 节点的 type 属性分类 未识别的类型按材料处理
+    pub fn from_type(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with("人工") {
+            RcjKind::Rg
+        } else if value.starts_with("机械") {
+            RcjKind::Jx
+        } else if value.starts_with("设备") {
+            RcjKind::Sb
+        } else if value.starts_with("主材") {
+            RcjKind::Zc
+        } else {
+            RcjKind::Cl
+        }
+    }
+    pub fn of(node: &Node) -> Self {
+        Self::from_type(&get_str(node, "type"))
+    }
+    /// 单价构成 计算基数中的费用代号
+    pub fn code(&self) -> &'static str {
+        match self {
+            RcjKind::Rg => "RGF",
+            RcjKind::Cl => "CLF",
+            RcjKind::Jx => "JXF",
+            RcjKind::Sb => "SBF",
+            RcjKind::Zc => "ZCF",
+        }
+    }
+}
+
+/// 单位价格构成
+/// 定额为每单位定额工程量的价格 清单为每单位清单工程量的价格
+/// 各项为未取整的精确值 序列化为字符串
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnitCost {
+    /// 人工费
+    pub rgf: Decimal,
+    /// 材料费
+    pub clf: Decimal,
+    /// 机械费
+    pub jxf: Decimal,
+    /// 设备费
+    pub sbf: Decimal,
+    /// 主材费
+    pub zcf: Decimal,
+    /// 管理费
+    pub glf: Decimal,
+    /// 利润
+    pub lr: Decimal,
+}
+
+impl UnitCost {
+    /// 综合单价
+    pub fn price(&self) -> Decimal {
+        self.rgf + self.clf + self.jxf + self.sbf + self.zcf + self.glf + self.lr
+    }
+    pub fn add_kind(&mut self, kind: RcjKind, value: Decimal) {
+        match kind {
+            RcjKind::Rg => self.rgf += value,
+            RcjKind::Cl => self.clf += value,
+            RcjKind::Jx => self.jxf += value,
+            RcjKind::Sb => self.sbf += value,
+            RcjKind::Zc => self.zcf += value,
+        }
+    }
+    /// 按系数累加 用于 定额单价 × 含量 汇总到清单
+    pub fn add_scaled(&mut self, other: &UnitCost, ratio: Decimal) {
+        self.rgf += other.rgf * ratio;
+        self.clf += other.clf * ratio;
+        self.jxf += other.jxf * ratio;
+        self.sbf += other.sbf * ratio;
+        self.zcf += other.zcf * ratio;
+        self.glf += other.glf * ratio;
+        self.lr += other.lr * ratio;
+    }
+    /// 计算基数变量表
+    fn bases(&self) -> HashMap<String, Decimal> {
+        HashMap::from([
+            ("RGF".to_string(), self.rgf),
+            ("CLF".to_string(), self.clf),
+            ("JXF".to_string(), self.jxf),
+            ("SBF".to_string(), self.sbf),
+            ("ZCF".to_string(), self.zcf),
+        ])
+    }
+}
+
+/// 单价构成行 计算结果
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DjgcRowCost {
+    pub id: NodeId,
+    pub code: String,
+    pub r#type: String,
+    pub desc: String,
+    pub caculate_base: String,
+    pub rate: Decimal,
+    pub price: Decimal,
+}
+
+/// 单位工程计价设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceOptions {
+    /// 甲供材料处理方式 include/exclude/deduct
+    pub jgcl_mode: String,
+    /// 价差处理方式 price/separate
+    pub jc_mode: String,
+    /// 工程量、单价、合价 取整规则
+    pub rounding: Rounding,
+}
+
+impl Default for PriceOptions {
+    fn default() -> Self {
+        Self {
+            jgcl_mode: JGCL_MODE_INCLUDE.to_string(),
+            jc_mode: JC_MODE_PRICE.to_string(),
+            rounding: Rounding::default(),
+        }
+    }
+}
+
+impl PriceOptions {
+    /// 读取节点所属单位工程的计价设置 不在单位工程下时使用默认设置
+    pub fn of(doc: &NodePool, id: &NodeId) -> Self {
+        let dwgc = match doc.get_node(id) {
+            Some(node) if node.r#type == DWGC_STR => Some(node),
+            _ => find_ancestor(doc, id, DWGC_STR),
+        };
+        match dwgc {
+            Some(dwgc) => {
+                let mut options = Self {
+                    rounding: Rounding::from_dwgc(&dwgc),
+                    ..Self::default()
+                };
+                let jgcl_mode = get_str(&dwgc, "jgclMode");
+                if !jgcl_mode.is_empty() {
+                    options.jgcl_mode = jgcl_mode;
+                }
+                let jc_mode = get_str(&dwgc, "jcMode");
+                if !jc_mode.is_empty() {
+                    options.jc_mode = jc_mode;
+                }
+                options
+            }
+            None => Self::default(),
+        }
+    }
+    /// 甲供材料是否计入综合单价
+    pub fn jgcl_in_price(&self) -> bool {
+        self.jgcl_mode != JGCL_MODE_EXCLUDE
+    }
+    /// 价差是否在费用汇总中单列
+    pub fn jc_separate(&self) -> bool {
+        self.jc_mode == JC_MODE_SEPARATE
+    }
+    /// 计入综合单价的人材机单价 价差单列时按定额基价计算
+    pub fn rcj_price(&self, rcj: &Node) -> Decimal {
+        if self.jc_separate() {
+            rcj_base_price(rcj)
+        } else {
+            rcj_price(rcj)
+        }
+    }
+}
+
+/// 人材机 单位消耗 价格
+pub fn rcj_price(rcj: &Node) -> Decimal {
+    get_decimal(rcj, "priceMarket")
+}
+
+/// 人材机 定额基价 未设置基价时视为与市场价相同(无价差)
+pub fn rcj_base_price(rcj: &Node) -> Decimal {
+    let price_base = get_decimal(rcj, "priceBase");
+    if price_base.is_zero() {
+        rcj_price(rcj)
+    } else {
+        price_base
+    }
+}
+
+/// 人材机 单位消耗 价差 = 市场价 - 定额基价
+pub fn rcj_jc(rcj: &Node) -> Decimal {
+    rcj_price(rcj) - rcj_base_price(rcj)
+}
+
+/// 是否暂估材料
+pub fn is_zgcl(rcj: &Node) -> bool {
+    get_bool(rcj, "ifProvisionalEstimate")
+}
+
+/// 是否甲供材料
+pub fn is_jgcl(rcj: &Node) -> bool {
+    get_bool(rcj, "ifDonorMaterial")
+}
+
+/// 清单、定额 是否锁定综合单价
+/// 锁定的行不参与调价、汇总时使用锁定时的单价构成 其组成不允许修改
+pub fn is_locked(node: &Node) -> bool {
+    (node.r#type == QD_STR || node.r#type == DE_STR) && get_bool(node, "lockPrice")
+}
+
+/// 锁定行的单价构成快照
+pub fn locked_cost(node: &Node) -> Option<UnitCost> {
+    if !is_locked(node) {
+        return None;
+    }
+    node.attrs.get_value::<UnitCost>("lockedCost")
+}
+
+/// 节点所属的已锁定 清单/定额(不含自身)
+pub fn locked_ancestor(doc: &NodePool, id: &NodeId) -> Option<Arc<Node>> {
+    let mut current = doc.get_parent_node(id);
+    while let Some(node) = current {
+        if is_locked(&node) {
+            return Some(node);
+        }
+        current = doc.get_parent_node(&node.id);
+    }
+    None
+}
+
+fn locked_error(node: &Node) -> String {
+    format!(
+        "{} {} 已锁定综合单价 不能修改其组成",
+        if node.r#type == QD_STR {
+            "清单"
+        } else {
+            "定额"
+        },
+        get_str(node, "projectCode")
+    )
+}
+
+/// 检查节点是否属于锁定行的组成 是则返回错误信息
+pub fn check_unlocked(doc: &NodePool, id: &NodeId) -> Result<(), String> {
+    match locked_ancestor(doc, id) {
+        Some(node) => Err(locked_error(&node)),
+        None => Ok(()),
+    }
+}
+
+/// 在节点下新增、删除子节点前检查 节点本身锁定或属于锁定行时返回错误信息
+pub fn check_children_unlocked(doc: &NodePool, parent_id: &NodeId) -> Result<(), String> {
+    match doc.get_node(parent_id) {
+        Some(parent) if is_locked(&parent) => Err(locked_error(&parent)),
+        _ => check_unlocked(doc, parent_id),
+    }
+}
+
+/// 定额暂估单价 = Σ 暂估材料 消耗量 × 单价
+pub fn de_zgf_price(doc: &NodePool, de_id: &NodeId) -> Decimal {
+    children_of_type(doc, de_id, RCJ_STR)
+        .iter()
+        .filter(|rcj| is_zgcl(rcj))
+        .map(|rcj| get_decimal(rcj, "resQty") * rcj_price(rcj))
+        .sum()
+}
+
+/// 计算定额的单位价格构成(使用所属单位工程的计价设置)
+pub fn de_unit_cost(doc: &NodePool, de_id: &NodeId) -> (UnitCost, Vec<DjgcRowCost>) {
+    de_unit_cost_with(doc, de_id, &PriceOptions::of(doc, de_id))
+}
+
+/// 计算定额的单位价格构成
+/// 人材机按 消耗量 × 市场价 分类累加 单价构成行按顺序计算 管理费、利润
+pub fn de_unit_cost_with(
+    doc: &NodePool,
+    de_id: &NodeId,
+    options: &PriceOptions,
+) -> (UnitCost, Vec<DjgcRowCost>) {
+    let mut cost = UnitCost::default();
+    let locked = doc.get_node(de_id).and_then(|de| locked_cost(&de));
+    for rcj in children_of_type(doc, de_id, RCJ_STR) {
+        if !options.jgcl_in_price() && is_jgcl(&rcj) {
+            continue;
+        }
+        cost.add_kind(
+            RcjKind::of(&rcj),
+            get_decimal(&rcj, "resQty") * options.rcj_price(&rcj),
+        );
+    }
+    let rows = djgc_rows(doc, de_id, &cost);
+    for row in rows.iter() {
+        if row.r#type == DJGC_TYPE_GLF {
+            cost.glf += row.price;
+        } else if row.r#type == DJGC_TYPE_LR {
+            cost.lr += row.price;
+        }
+    }
+    //锁定的定额 单价构成固定为锁定时的值
+    (locked.unwrap_or(cost), rows)
+}
+
+/// 计算清单的单位价格构成 各定额按含量折算后累加
+pub fn qd_unit_cost_with(doc: &NodePool, qd_id: &NodeId, options: &PriceOptions) -> UnitCost {
+    let mut cost = UnitCost::default();
+    let qd_quantity = match doc.get_node(qd_id) {
+        Some(qd) => match locked_cost(&qd) {
+            Some(locked) => return locked,
+            None => get_decimal(&qd, "quantity"),
+        },
+        None => return cost,
+    };
+    if qd_quantity.is_zero() {
+        return cost;
+    }
+    for de in children_of_type(doc, qd_id, DE_STR) {
+        let (de_cost, _) = de_unit_cost_with(doc, &de.id, options);
+        cost.add_scaled(&de_cost, get_decimal(&de, "quantity") / qd_quantity);
+    }
+    cost
+}
+
+/// 清单 工程量、综合单价、合价 按所属单位工程的取整规则计算
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QdAmount {
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub total: Decimal,
+}
+
+pub fn qd_amount(doc: &NodePool, qd: &Node, options: &PriceOptions) -> QdAmount {
+    let rounding = &options.rounding;
+    let unit = get_str(qd, "unit");
+    let quantity = get_decimal(qd, "quantity");
+    let price = qd_unit_cost_with(doc, &qd.id, options).price();
+    QdAmount {
+        quantity: rounding.quantity(&unit, quantity),
+        price: rounding.price(price),
+        total: rounding.amount(price, quantity, &unit),
+    }
+}
+
+/// 计算定额下 单价构成 各行的金额
+/// 计算基数支持 费用代号(RGF/CLF/JXF/SBF/ZCF 及前序行的 code) 与数字的加减 费率为百分比
+fn djgc_rows(doc: &NodePool, de_id: &NodeId, cost: &UnitCost) -> Vec<DjgcRowCost> {
+    let mut bases = cost.bases();
+    let mut result = Vec::new();
+    for djgc in children_of_type(doc, de_id, DJGC_STR) {
+        for row in children_of_type(doc, &djgc.id, DJGC_ROW_STR) {
+            let caculate_base = get_str(&row, "caculateBase");
+            let rate = get_decimal(&row, "rate");
+            let price = if caculate_base.trim().is_empty() {
+                get_decimal(&row, "price")
+            } else {
+                eval_base(&caculate_base, &bases) * rate / Decimal::ONE_HUNDRED
+            };
+            let code = get_str(&row, "code");
+            if !code.is_empty() {
+                bases.insert(code.to_uppercase(), price);
+            }
+            result.push(DjgcRowCost {
+                id: row.id.clone(),
+                code,
+                r#type: get_str(&row, "type"),
+                desc: get_str(&row, "desc"),
+                caculate_base,
+                rate,
+                price,
+            });
+        }
+    }
+    result
+}
+
+/// 计算基数求值 只支持加减
+fn eval_base(expr: &str, bases: &HashMap<String, Decimal>) -> Decimal {
+    let mut total = Decimal::ZERO;
+    let mut sign = Decimal::ONE;
+    let mut token = String::new();
+    let flush = |token: &mut String, sign: Decimal, total: &mut Decimal| {
+        let key = token.trim().to_uppercase();
+        if !key.is_empty() {
+            let value = Decimal::from_str(&key)
+                .unwrap_or_else(|_| bases.get(&key).copied().unwrap_or_default());
+            *total += sign * value;
+        }
+        token.clear();
+    };
+    for ch in expr.chars() {
+        match ch {
+            '+' | '-' => {
+                flush(&mut token, sign, &mut total);
+                sign = if ch == '+' {
+                    Decimal::ONE
+                } else {
+                    Decimal::NEGATIVE_ONE
+                };
+            }
+            _ => token.push(ch),
+        }
+    }
+    flush(&mut token, sign, &mut total);
+    total
+}
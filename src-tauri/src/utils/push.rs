@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use mf_model::{node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{plugins::inc::Operation, utils::clipboard::is_self_or_descendant};
+
+/// 推送通道容量 订阅方处理不过来时丢弃旧事件并通知其重新同步
+const PUSH_CAPACITY: usize = 256;
+
+lazy_static! {
+    static ref INC_CHANNEL: broadcast::Sender<IncEvent> = broadcast::channel(PUSH_CAPACITY).0;
+    /// 窗口订阅 key 为 Tauri 窗口 label
+    static ref WINDOW_SUBSCRIPTIONS: DashMap<String, Vec<IncSubscription>> = DashMap::new();
+}
+
+/// 一个事务产生的增量数据 附带事务前后的文档用于按子树过滤
+#[derive(Debug, Clone)]
+pub struct IncEvent {
+    pub editor_name: String,
+    pub version: u64,
+    pub operations: Arc<Vec<Operation>>,
+    pub doc: Arc<NodePool>,
+    pub old_doc: Arc<NodePool>,
+}
+
+/// 推送给客户端的增量数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncPush {
+    pub editor_name: String,
+    /// 订阅的子树根节点
+    pub root_id: Option<NodeId>,
+    pub version: u64,
+    pub operations: Vec<Operation>,
+}
+
+/// 订阅条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncSubscription {
+    pub editor_name: String,
+    /// 只接收该节点子树内的变更 为空时接收整个工程项目的变更
+    pub root_id: Option<NodeId>,
+}
+
+impl IncSubscription {
+    /// 按订阅条件过滤 没有相关变更时返回 None
+    /// 节点在事务前或事务后位于子树内即视为相关 删除、移出子树的变更同样推送
+    pub fn filter(&self, event: &IncEvent) -> Option<IncPush> {
+        if event.editor_name != self.editor_name {
+            return None;
+        }
+        let operations: Vec<Operation> = match &self.root_id {
+            None => event.operations.as_ref().clone(),
+            Some(root_id) => event
+                .operations
+                .iter()
+                .filter(|operation| {
+                    operation.node_ids().into_iter().any(|id| {
+                        is_self_or_descendant(&event.doc, id, root_id)
+                            || is_self_or_descendant(&event.old_doc, id, root_id)
+                    })
+                })
+                .cloned()
+                .collect(),
+        };
+        if operations.is_empty() {
+            return None;
+        }
+        Some(IncPush {
+            editor_name: event.editor_name.clone(),
+            root_id: self.root_id.clone(),
+            version: event.version,
+            operations,
+        })
+    }
+}
+
+/// 发布增量数据 没有订阅方时直接丢弃
+pub fn publish(event: IncEvent) {
+    let _ = INC_CHANNEL.send(event);
+}
+
+/// 订阅全部工程项目的增量数据
+pub fn subscribe() -> broadcast::Receiver<IncEvent> {
+    INC_CHANNEL.subscribe()
+}
+
+/// 添加窗口订阅 同一窗口可订阅多个子树
+pub fn add_window_subscription(label: &str, subscription: IncSubscription) {
+    let mut subscriptions = WINDOW_SUBSCRIPTIONS.entry(label.to_string()).or_default();
+    if !subscriptions.contains(&subscription) {
+        subscriptions.push(subscription);
+    }
+}
+
+/// 取消窗口订阅 subscription 为空时取消该窗口全部订阅
+pub fn remove_window_subscription(label: &str, subscription: Option<&IncSubscription>) {
+    match subscription {
+        Some(subscription) => {
+            if let Some(mut subscriptions) = WINDOW_SUBSCRIPTIONS.get_mut(label) {
+                subscriptions.retain(|item| item != subscription);
+            }
+        }
+        None => {
+            WINDOW_SUBSCRIPTIONS.remove(label);
+        }
+    }
+}
+
+/// 已订阅增量数据的窗口 label
+pub fn subscribed_windows() -> Vec<String> {
+    WINDOW_SUBSCRIPTIONS
+        .iter()
+        .filter(|item| !item.value().is_empty())
+        .map(|item| item.key().clone())
+        .collect()
+}
+
+/// 按窗口订阅过滤事件 返回 (窗口 label, 推送数据)
+pub fn window_pushes(event: &IncEvent) -> Vec<(String, IncPush)> {
+    let mut pushes = Vec::new();
+    for item in WINDOW_SUBSCRIPTIONS.iter() {
+        for subscription in item.value().iter() {
+            if let Some(push) = subscription.filter(event) {
+                pushes.push((item.key().clone(), push));
+            }
+        }
+    }
+    pushes
+}
@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+
+use mf_model::{node::Node, node_pool::NodePool, types::NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    nodes::{
+        fbfx_csxm::{FB_STR, QD_STR},
+        gcxm::{CODE_MODE_AUTO, DWGC_STR, GCXM_STR},
+    },
+    utils::node::{children, descendants_of_type, find_ancestor, get_str},
+};
+
+/// 项目编码属性
+pub const CODE_ATTR: &str = "projectCode";
+/// 清单编码 前 9 位 全国统一编码
+const QD_BASE_LEN: usize = 9;
+/// 清单编码 后 3 位 单位工程内顺序码
+const QD_SEQ_LEN: usize = 3;
+
+/// 项目编码方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeMode {
+    /// 保留手工编码 仅补全缺少的编码
+    Manual,
+    /// 按树顺序重排全部编码
+    Auto,
+}
+
+impl CodeMode {
+    /// 读取节点所在工程项目的编码方式
+    pub fn of(doc: &NodePool, id: &NodeId) -> Self {
+        let gcxm = match doc.get_node(id) {
+            Some(node) if node.r#type == GCXM_STR => Some(node),
+            _ => find_ancestor(doc, id, GCXM_STR),
+        };
+        match gcxm {
+            Some(gcxm) if get_str(&gcxm, "codeMode") == CODE_MODE_AUTO => CodeMode::Auto,
+            _ => CodeMode::Manual,
+        }
+    }
+}
+
+/// 重复的清单编码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XmbmDuplicate {
+    pub code: String,
+    pub ids: Vec<NodeId>,
+}
+
+/// 清单编码前 9 位 不是数字时返回 None
+pub fn qd_base(code: &str) -> Option<String> {
+    let base: String = code.chars().take(QD_BASE_LEN).collect();
+    if base.chars().count() == QD_BASE_LEN && base.chars().all(|c| c.is_ascii_digit()) {
+        Some(base)
+    } else {
+        None
+    }
+}
+
+/// 完整的 12 位清单编码
+fn is_full_qd_code(code: &str) -> bool {
+    code.len() == QD_BASE_LEN + QD_SEQ_LEN && code.chars().all(|c| c.is_ascii_digit())
+}
+
+/// 节点所在单位工程 节点本身为单位工程时返回自身
+pub fn dwgc_of(doc: &NodePool, id: &NodeId) -> Option<NodeId> {
+    match doc.get_node(id) {
+        Some(node) if node.r#type == DWGC_STR => Some(node.id.clone()),
+        Some(_) => find_ancestor(doc, id, DWGC_STR).map(|dwgc| dwgc.id.clone()),
+        None => None,
+    }
+}
+
+/// 节点下的全部单位工程 节点本身为单位工程时返回自身
+pub fn dwgc_ids(doc: &NodePool, id: &NodeId) -> Vec<NodeId> {
+    match doc.get_node(id) {
+        Some(node) if node.r#type == DWGC_STR => vec![node.id.clone()],
+        Some(_) => descendants_of_type(doc, id, &[DWGC_STR])
+            .iter()
+            .map(|dwgc| dwgc.id.clone())
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// 单位工程编码计算
+struct Numbering<'a> {
+    doc: &'a NodePool,
+    mode: CodeMode,
+    /// 各 9 位编码已使用的顺序码
+    used: HashMap<String, HashSet<u32>>,
+    /// 需要回填的编码
+    changes: Vec<(NodeId, String)>,
+}
+
+impl<'a> Numbering<'a> {
+    /// 手工模式下 先登记已有的完整编码 补全时跳过已使用的顺序码
+    fn new(doc: &'a NodePool, dwgc_id: &NodeId, mode: CodeMode) -> Self {
+        let mut used: HashMap<String, HashSet<u32>> = HashMap::new();
+        if mode == CodeMode::Manual {
+            for qd in descendants_of_type(doc, dwgc_id, &[QD_STR]) {
+                let code = get_str(&qd, CODE_ATTR);
+                if is_full_qd_code(&code) {
+                    let seq = code[QD_BASE_LEN..].parse::<u32>().unwrap_or_default();
+                    used.entry(code[..QD_BASE_LEN].to_string())
+                        .or_default()
+                        .insert(seq);
+                }
+            }
+        }
+        Self {
+            doc,
+            mode,
+            used,
+            changes: Vec::new(),
+        }
+    }
+
+    /// 下一个未使用的顺序码
+    fn next_seq(&mut self, base: &str) -> Result<u32, String> {
+        let used = self.used.entry(base.to_string()).or_default();
+        let seq = (1..1000).find(|seq| !used.contains(seq));
+        match seq {
+            Some(seq) => {
+                used.insert(seq);
+                Ok(seq)
+            }
+            None => Err(format!("清单编码 {} 的顺序码已超过 999", base)),
+        }
+    }
+
+    fn set_code(&mut self, node: &Node, code: String) {
+        if get_str(node, CODE_ATTR) != code {
+            self.changes.push((node.id.clone(), code));
+        }
+    }
+
+    /// 按树顺序编码 分部编码为上级分部编码加两位序号
+    fn walk(&mut self, parent_id: &NodeId, fb_code: &str) -> Result<(), String> {
+        let mut fb_index = 0;
+        for child in children(self.doc, parent_id) {
+            if child.r#type == FB_STR {
+                fb_index += 1;
+                let current = get_str(&child, CODE_ATTR);
+                let code = if self.mode == CodeMode::Auto || current.trim().is_empty() {
+                    let code = format!("{}{:02}", fb_code, fb_index);
+                    self.set_code(&child, code.clone());
+                    code
+                } else {
+                    current
+                };
+                self.walk(&child.id, &code)?;
+            } else if child.r#type == QD_STR {
+                let current = get_str(&child, CODE_ATTR);
+                let base = match qd_base(current.trim()) {
+                    Some(base) => base,
+                    // 没有 9 位编码的清单(如补充清单) 不参与编码
+                    None => continue,
+                };
+                if self.mode == CodeMode::Manual && current.trim().len() != QD_BASE_LEN {
+                    continue;
+                }
+                let seq = self.next_seq(&base)?;
+                let code = format!("{}{:0width$}", base, seq, width = QD_SEQ_LEN);
+                self.set_code(&child, code);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 计算单位工程的项目编码 返回需要修改的 (节点, 编码)
+/// 清单编码 = 9 位编码 + 单位工程内按树顺序递增的 3 位顺序码
+pub fn renumber(
+    doc: &NodePool,
+    dwgc_id: &NodeId,
+    mode: CodeMode,
+) -> Result<Vec<(NodeId, String)>, String> {
+    let mut numbering = Numbering::new(doc, dwgc_id, mode);
+    // 分部分项、措施项目 分别编码 清单顺序码在单位工程内连续
+    for area in children(doc, dwgc_id) {
+        numbering.walk(&area.id, "")?;
+    }
+    Ok(numbering.changes)
+}
+
+/// 单位工程内重复的清单编码
+pub fn find_duplicates(doc: &NodePool, dwgc_id: &NodeId) -> Vec<XmbmDuplicate> {
+    let mut codes: Vec<(String, Vec<NodeId>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for qd in descendants_of_type(doc, dwgc_id, &[QD_STR]) {
+        let code = get_str(&qd, CODE_ATTR).trim().to_string();
+        if code.is_empty() {
+            continue;
+        }
+        match index.get(&code) {
+            Some(i) => codes[*i].1.push(qd.id.clone()),
+            None => {
+                index.insert(code.clone(), codes.len());
+                codes.push((code, vec![qd.id.clone()]));
+            }
+        }
+    }
+    codes
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(code, ids)| XmbmDuplicate { code, ids })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mf_model::{node::Node, node_pool::NodePool, node_type::NodeEnum};
+    use serde_json::json;
+
+    use super::{find_duplicates, renumber, CodeMode};
+    use crate::nodes::{
+        fbfx_csxm::{CSXM_STR, FBFX_STR, FB_STR, QD_STR},
+        gcxm::DWGC_STR,
+    };
+
+    fn node(id: &str, r#type: &str, code: &str, children: Vec<NodeEnum>) -> NodeEnum {
+        let content = children.iter().map(|child| child.0.id.clone()).collect();
+        NodeEnum(
+            Node::new(
+                id,
+                r#type.to_string(),
+                serde_json::from_value(json!({ "projectCode": code })).unwrap(),
+                content,
+                vec![],
+            ),
+            children,
+        )
+    }
+
+    fn qd(id: &str, code: &str) -> NodeEnum {
+        node(id, QD_STR, code, vec![])
+    }
+
+    /// 单位工程 下为 分部分项 与 措施项目
+    fn doc(fbfx: Vec<NodeEnum>, csxm: Vec<NodeEnum>) -> NodePool {
+        NodePool::from(node(
+            "dwgc",
+            DWGC_STR,
+            "",
+            vec![
+                node("fbfx", FBFX_STR, "", fbfx),
+                node("csxm", CSXM_STR, "", csxm),
+            ],
+        ))
+        .as_ref()
+        .clone()
+    }
+
+    fn changes(doc: &NodePool, mode: CodeMode) -> Vec<(String, String)> {
+        renumber(doc, &"dwgc".to_string(), mode).unwrap()
+    }
+
+    fn change(id: &str, code: &str) -> (String, String) {
+        (id.to_string(), code.to_string())
+    }
+
+    #[test]
+    fn manual_keeps_full_codes() {
+        let doc = doc(
+            vec![qd("a", "010101001003"), qd("b", "010101001001")],
+            vec![],
+        );
+        assert!(changes(&doc, CodeMode::Manual).is_empty());
+    }
+
+    #[test]
+    fn manual_fills_nine_digit_codes() {
+        let doc = doc(
+            vec![
+                qd("a", "010101001001"),
+                qd("b", "010101001"),
+                qd("c", "010101002"),
+                qd("d", "补充清单"),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            changes(&doc, CodeMode::Manual),
+            vec![change("b", "010101001002"), change("c", "010101002001")]
+        );
+    }
+
+    #[test]
+    fn auto_renumbers_in_tree_order() {
+        let doc = doc(
+            vec![
+                node("fb1", FB_STR, "09", vec![qd("a", "010101001005")]),
+                node("fb2", FB_STR, "", vec![qd("b", "010101001001")]),
+            ],
+            vec![],
+        );
+        assert_eq!(
+            changes(&doc, CodeMode::Auto),
+            vec![
+                change("fb1", "01"),
+                change("a", "010101001001"),
+                change("fb2", "02"),
+                change("b", "010101001002"),
+            ]
+        );
+    }
+
+    #[test]
+    fn seq_continues_across_fbfx_and_csxm() {
+        let doc = doc(vec![qd("a", "011701001")], vec![qd("b", "011701001")]);
+        assert_eq!(
+            changes(&doc, CodeMode::Auto),
+            vec![change("a", "011701001001"), change("b", "011701001002")]
+        );
+    }
+
+    #[test]
+    fn seq_past_999_is_error() {
+        let mut fbfx: Vec<NodeEnum> = (1..1000)
+            .map(|seq| qd(&format!("q{}", seq), &format!("010101001{:03}", seq)))
+            .collect();
+        fbfx.push(qd("new", "010101001"));
+        let doc = doc(fbfx, vec![]);
+        assert!(renumber(&doc, &"dwgc".to_string(), CodeMode::Manual).is_err());
+    }
+
+    #[test]
+    fn duplicates_in_dwgc() {
+        let doc = doc(
+            vec![
+                qd("a", "010101001001"),
+                qd("b", "010101001002"),
+                qd("c", ""),
+            ],
+            vec![qd("d", "010101001001"), qd("e", "")],
+        );
+        let duplicates = find_duplicates(&doc, &"dwgc".to_string());
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].code, "010101001001");
+        assert_eq!(duplicates[0].ids, vec!["a".to_string(), "d".to_string()]);
+    }
+}